@@ -1,7 +1,7 @@
 use sp_core::{Pair, Public, sr25519};
 use canvas_runtime::{
-	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig,
-	SudoConfig, SystemConfig, WASM_BINARY, Signature,
+	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig, Multisig,
+	ParamsConfig, RentMode, SudoConfig, SystemConfig, WASM_BINARY, Signature,
 };
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_finality_grandpa::AuthorityId as GrandpaId;
@@ -39,6 +39,21 @@ pub fn authority_keys_from_seed(s: &str) -> (AuraId, GrandpaId) {
 	)
 }
 
+/// Helper function to derive the multisig account of `signatories` with the given `threshold`.
+///
+/// This is the account `pallet_multisig` dispatches from once `threshold` of the
+/// `signatories` approved a call, so it can be used as the sudo key at genesis.
+pub fn multisig_account_id(mut signatories: Vec<AccountId>, threshold: u16) -> AccountId {
+	// `pallet_multisig` requires the other signatories to be passed in sorted order.
+	signatories.sort();
+	signatories.dedup();
+	assert!(
+		threshold >= 1 && threshold as usize <= signatories.len(),
+		"the threshold must be between 1 and the number of signatories",
+	);
+	Multisig::multi_account_id(&signatories, threshold)
+}
+
 pub fn testnet_authorities() -> Vec<(AuraId, GrandpaId)> {
 	use sp_core::crypto::UncheckedInto;
 
@@ -116,6 +131,49 @@ pub fn development_config() -> Result<ChainSpec, String> {
 	))
 }
 
+/// The development chain, but with the sudo key shared by Alice, Bob and Charlie.
+///
+/// Any two of them have to approve a sudo call through `pallet_multisig`.
+pub fn multisig_development_config() -> Result<ChainSpec, String> {
+	let wasm_binary = WASM_BINARY.ok_or("Development wasm binary not available".to_string())?;
+
+	Ok(ChainSpec::from_genesis(
+		"Multisig Development",
+		"multisig_dev",
+		ChainType::Development,
+		move || testnet_genesis(
+			wasm_binary,
+			vec![
+				authority_keys_from_seed("Alice"),
+			],
+			multisig_development_root(),
+			vec![
+				get_account_id_from_seed::<sr25519::Public>("Alice"),
+				get_account_id_from_seed::<sr25519::Public>("Bob"),
+				get_account_id_from_seed::<sr25519::Public>("Charlie"),
+				get_account_id_from_seed::<sr25519::Public>("Dave"),
+			],
+		),
+		vec![],
+		None,
+		None,
+		None,
+		None,
+	))
+}
+
+/// The 2-of-3 multisig account of Alice, Bob and Charlie.
+pub fn multisig_development_root() -> AccountId {
+	multisig_account_id(
+		vec![
+			get_account_id_from_seed::<sr25519::Public>("Alice"),
+			get_account_id_from_seed::<sr25519::Public>("Bob"),
+			get_account_id_from_seed::<sr25519::Public>("Charlie"),
+		],
+		2,
+	)
+}
+
 pub fn testnet_config() -> Result<ChainSpec, String> {
 	let wasm_binary = WASM_BINARY.ok_or("Development wasm binary not available".to_string())?;
 	let mut properties = sc_service::Properties::new();
@@ -185,5 +243,54 @@ fn testnet_genesis(
 			// Assign network admin rights.
 			key: root_key,
 		},
+		params: ParamsConfig {
+			// Contracts are neither charged rent nor evicted.
+			rent_mode: Some(RentMode::Disabled),
+			// Blocks are produced every `SLOT_DURATION`.
+			minimum_period: None,
+		},
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn account(seed: &str) -> AccountId {
+		get_account_id_from_seed::<sr25519::Public>(seed)
+	}
+
+	#[test]
+	fn multisig_account_id_does_not_depend_on_the_order_of_signatories() {
+		let sorted = multisig_account_id(vec![account("Alice"), account("Bob"), account("Charlie")], 2);
+		let unsorted = multisig_account_id(vec![account("Charlie"), account("Alice"), account("Bob")], 2);
+		assert_eq!(sorted, unsorted);
+		assert_eq!(
+			multisig_account_id(vec![account("Alice"), account("Bob"), account("Bob")], 2),
+			multisig_account_id(vec![account("Alice"), account("Bob")], 2),
+		);
+	}
+
+	#[test]
+	fn multisig_account_id_depends_on_the_threshold() {
+		let signatories = vec![account("Alice"), account("Bob"), account("Charlie")];
+		assert_ne!(
+			multisig_account_id(signatories.clone(), 2),
+			multisig_account_id(signatories, 3),
+		);
+	}
+
+	#[test]
+	fn multisig_development_root_is_the_multisig_account() {
+		let mut signatories = vec![account("Alice"), account("Bob"), account("Charlie")];
+		signatories.sort();
+		assert_eq!(multisig_development_root(), Multisig::multi_account_id(&signatories, 2));
+		assert!(!signatories.contains(&multisig_development_root()));
+	}
+
+	#[test]
+	#[should_panic(expected = "the threshold must be between 1 and the number of signatories")]
+	fn multisig_account_id_rejects_an_unreachable_threshold() {
+		multisig_account_id(vec![account("Alice"), account("Bob")], 3);
 	}
 }
@@ -0,0 +1,303 @@
+//! The `check-contract` subcommand.
+//!
+//! Checks whether a contract would be accepted by the chain, without submitting
+//! anything. The limits of the `Schedule` which are most often violated (the code
+//! size, the memory pages and the floating point instructions, which have no weight
+//! and are therefore unsupported) are checked first to report the specific violation.
+//! Afterwards the code is uploaded in a dry-run against the state of the best block,
+//! so it is validated and instrumented with the `Schedule` of the runtime in use.
+
+use std::{fmt, fs, path::PathBuf, sync::Arc};
+use canvas_runtime::{
+	opaque::Block, AccountId, Balance, BlockNumber, ContractsError, Hash, Runtime,
+	RuntimeBlockWeights, Schedule,
+};
+use parity_wasm::elements::{External, Instruction, Module, Type, ValueType};
+use log::info;
+use pallet_contracts_primitives::Code;
+use pallet_contracts_rpc::ContractsRuntimeApi;
+use sc_cli::{CliConfiguration, DatabaseParams, PruningParams, SharedParams};
+use sc_client_api::UsageProvider;
+use sp_api::ProvideRuntimeApi;
+use sp_runtime::{generic::BlockId, traits::Get, DispatchError};
+use structopt::StructOpt;
+
+/// The `check-contract` command used to validate a contract against the schedule.
+#[derive(Debug, StructOpt)]
+pub struct CheckContractCmd {
+	/// Path to the wasm blob of the contract.
+	#[structopt(parse(from_os_str))]
+	pub wasm: PathBuf,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+/// A limit of the `Schedule` which a contract violates.
+#[derive(Debug, PartialEq)]
+pub enum Violation {
+	/// The code is larger than the maximum code size.
+	CodeSize { len: u32, max: u32 },
+	/// The imported memory may grow beyond the maximum memory pages, or it has no maximum.
+	MemoryPages { declared: Option<u32>, max: u32 },
+	/// The contract defines its own memory instead of importing it from the environment.
+	InternalMemory,
+	/// The contract uses an instruction which is not supported, e.g. a floating point one.
+	Opcode(String),
+	/// The contract uses floating point values, which are not supported.
+	FloatingPoint,
+	/// The code is no valid wasm module.
+	Invalid(String),
+}
+
+impl fmt::Display for Violation {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Violation::CodeSize { len, max } =>
+				write!(f, "code size: the code has {} bytes but at most {} are allowed", len, max),
+			Violation::MemoryPages { declared: Some(declared), max } => write!(
+				f,
+				"memory pages: the memory may grow to {} pages but at most {} are allowed",
+				declared,
+				max,
+			),
+			Violation::MemoryPages { declared: None, max } => write!(
+				f,
+				"memory pages: the memory declares no maximum but at most {} pages are allowed",
+				max,
+			),
+			Violation::InternalMemory =>
+				write!(f, "memory: the memory must be imported from the environment"),
+			Violation::Opcode(instruction) =>
+				write!(f, "opcode: the instruction {} is not supported", instruction),
+			Violation::FloatingPoint =>
+				write!(f, "opcode: floating point values are not supported"),
+			Violation::Invalid(e) => write!(f, "the code is no valid wasm module: {}", e),
+		}
+	}
+}
+
+/// Checks `code` against the limits of `schedule` which can be checked without executing it.
+pub fn check_limits(
+	code: &[u8],
+	schedule: &pallet_contracts::Schedule<Runtime>,
+) -> Result<(), Violation> {
+	let limits = &schedule.limits;
+	if code.len() > limits.code_size as usize {
+		return Err(Violation::CodeSize { len: code.len() as u32, max: limits.code_size });
+	}
+
+	let module: Module = parity_wasm::deserialize_buffer(code)
+		.map_err(|e| Violation::Invalid(e.to_string()))?;
+
+	if module.memory_section().map_or(false, |memories| !memories.entries().is_empty()) {
+		return Err(Violation::InternalMemory);
+	}
+	let memory = module.import_section()
+		.into_iter()
+		.flat_map(|imports| imports.entries())
+		.find_map(|import| match import.external() {
+			External::Memory(memory) => Some(memory.limits().maximum()),
+			_ => None,
+		});
+	if let Some(declared) = memory {
+		if declared.map_or(true, |pages| pages > limits.memory_pages) {
+			return Err(Violation::MemoryPages { declared, max: limits.memory_pages });
+		}
+	}
+
+	let is_float = |value: &ValueType| matches!(value, ValueType::F32 | ValueType::F64);
+	let float_signature = module.type_section()
+		.into_iter()
+		.flat_map(|types| types.types())
+		.any(|Type::Function(function)| {
+			function.params().iter().chain(function.results()).any(is_float)
+		});
+	let float_global = module.global_section()
+		.into_iter()
+		.flat_map(|globals| globals.entries())
+		.any(|global| is_float(&global.global_type().content_type()));
+	let bodies = module.code_section().map_or(&[][..], |code| code.bodies());
+	let float_local = bodies.iter()
+		.flat_map(|body| body.locals())
+		.any(|local| is_float(&local.value_type()));
+	if float_signature || float_global || float_local {
+		return Err(Violation::FloatingPoint);
+	}
+
+	let unsupported = bodies.iter()
+		.flat_map(|body| body.code().elements())
+		.find(|instruction| is_unsupported(instruction));
+	if let Some(instruction) = unsupported {
+		return Err(Violation::Opcode(format!("{:?}", instruction)));
+	}
+	Ok(())
+}
+
+/// Whether the `Schedule` assigns no weight to `instruction`, i.e. whether it operates on
+/// floating point values.
+fn is_unsupported(instruction: &Instruction) -> bool {
+	let name = format!("{:?}", instruction);
+	name.contains("F32") || name.contains("F64")
+}
+
+/// Whether `error` is the `error` of the contracts pallet.
+fn is_contracts_error(error: &DispatchError, expected: ContractsError<Runtime>) -> bool {
+	match (error, DispatchError::from(expected)) {
+		(
+			DispatchError::Module { index, error, .. },
+			DispatchError::Module { index: expected_index, error: expected_error, .. },
+		) => *index == expected_index && *error == expected_error,
+		_ => false,
+	}
+}
+
+impl CheckContractCmd {
+	/// Run the check-contract command
+	pub async fn run<C>(&self, client: Arc<C>) -> sc_cli::Result<()>
+	where
+		C: UsageProvider<Block> + ProvideRuntimeApi<Block>,
+		C::Api: ContractsRuntimeApi<Block, AccountId, Balance, BlockNumber, Hash>,
+	{
+		let code = fs::read(&self.wasm)?;
+		let at = BlockId::Hash(client.usage_info().chain.best_hash);
+		info!("Checking {} ({} bytes) at {}...", self.wasm.display(), code.len(), at);
+
+		check_limits(&code, &Schedule::get())
+			.map_err(|violation| format!("{}: {}", self.wasm.display(), violation))?;
+
+		// The code is validated before anything is transferred or executed, so the
+		// origin and the endowment do not matter.
+		let result = client.runtime_api().instantiate(
+			&at,
+			AccountId::from([0u8; 32]),
+			0,
+			RuntimeBlockWeights::get().max_block,
+			Code::Upload(code.into()),
+			Vec::new(),
+			Vec::new(),
+		).map_err(|e| format!("Failed to run the dry-run: {:?}", e))?;
+
+		let error = match result.result {
+			Ok(_) => None,
+			Err(error) if is_contracts_error(&error, ContractsError::CodeTooLarge) =>
+				Some("the code exceeds the maximum code size of the schedule".to_string()),
+			Err(error) if is_contracts_error(&error, ContractsError::CodeRejected) => Some(
+				"the code was rejected by the validation against the schedule (e.g. because \
+				of its imports, tables or globals); run with `-lruntime::contracts=debug` \
+				for the reason".to_string()
+			),
+			// Any later failure means that the code itself was accepted.
+			Err(_) => None,
+		};
+
+		match error {
+			None => {
+				println!("{}: OK", self.wasm.display());
+				Ok(())
+			},
+			Some(error) => Err(format!("{}: {}", self.wasm.display(), error).into()),
+		}
+	}
+}
+
+impl CliConfiguration for CheckContractCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A contract which passes all checks.
+	const VALID: &str = r#"
+(module
+	(import "env" "memory" (memory 1 16))
+	(func (export "deploy"))
+	(func (export "call"))
+)
+"#;
+
+	fn check(wat: &str) -> Result<(), Violation> {
+		check_limits(&wat::parse_str(wat).unwrap(), &Schedule::get())
+	}
+
+	#[test]
+	fn valid_contract_passes() {
+		assert_eq!(check(VALID), Ok(()));
+	}
+
+	#[test]
+	fn oversized_contract_is_rejected() {
+		let max = Schedule::get().limits.code_size;
+		let oversized = format!(r#"
+(module
+	(import "env" "memory" (memory 1 16))
+	(data (i32.const 0) "{}")
+	(func (export "deploy"))
+	(func (export "call"))
+)
+"#, "\\00".repeat(max as usize));
+
+		let len = wat::parse_str(&oversized).unwrap().len() as u32;
+		assert_eq!(check(&oversized), Err(Violation::CodeSize { len, max }));
+	}
+
+	#[test]
+	fn too_many_memory_pages_are_rejected() {
+		let max = Schedule::get().limits.memory_pages;
+		let wat = VALID.replace("(memory 1 16)", &format!("(memory 1 {})", max + 1));
+		assert_eq!(check(&wat), Err(Violation::MemoryPages { declared: Some(max + 1), max }));
+
+		let unbounded = VALID.replace("(memory 1 16)", "(memory 1)");
+		assert_eq!(check(&unbounded), Err(Violation::MemoryPages { declared: None, max }));
+	}
+
+	#[test]
+	fn internal_memory_is_rejected() {
+		let wat = VALID.replace(r#"(import "env" "memory" (memory 1 16))"#, "(memory 1 16)");
+		assert_eq!(check(&wat), Err(Violation::InternalMemory));
+	}
+
+	#[test]
+	fn floating_point_instructions_are_rejected() {
+		let wat = VALID.replace(
+			r#"(func (export "call"))"#,
+			r#"(func (export "call") (drop (f32.const 1)))"#,
+		);
+		assert!(matches!(
+			check(&wat),
+			Err(Violation::Opcode(instruction)) if instruction.starts_with("F32Const")
+		));
+
+		let wat = VALID.replace(
+			r#"(func (export "call"))"#,
+			r#"(func (export "call") (local f64))"#,
+		);
+		assert_eq!(check(&wat), Err(Violation::FloatingPoint));
+	}
+
+	#[test]
+	fn garbage_is_rejected() {
+		assert!(matches!(check_limits(b"no wasm", &Schedule::get()), Err(Violation::Invalid(_))));
+	}
+}
@@ -1,5 +1,4 @@
 use structopt::StructOpt;
-use sc_cli::RunCmd;
 
 #[derive(Debug, StructOpt)]
 pub struct Cli {
@@ -10,6 +9,78 @@ pub struct Cli {
 	pub run: RunCmd,
 }
 
+/// The `run` command used to run a Canvas node.
+#[derive(Debug, StructOpt)]
+pub struct RunCmd {
+	#[structopt(flatten)]
+	pub base: sc_cli::RunCmd,
+
+	/// Specify a port for an additional HTTP RPC server which only exposes
+	/// the read-only contracts and state methods.
+	///
+	/// Unsafe methods are always denied on this server. It listens on the same
+	/// interface as the regular HTTP RPC server.
+	#[structopt(long = "contracts-rpc-port", value_name = "PORT")]
+	pub contracts_rpc_port: Option<u16>,
+
+	/// Log a summary of the contract instantiations and calls in every block
+	/// authored by this node.
+	#[structopt(long = "log-contract-activity")]
+	pub log_contract_activity: bool,
+
+	/// The lowest `gas_limit` accepted by the `contracts_call` and
+	/// `contracts_instantiate` RPC methods.
+	///
+	/// Dry-runs with a lower limit are rejected without executing them, since
+	/// they would run out of gas anyway. Set to 0 to disable the check.
+	#[structopt(long = "contracts-min-gas-limit", value_name = "GAS", default_value = "100000000")]
+	pub contracts_min_gas_limit: u64,
+
+	/// Report the contract instantiations, calls and gas of every new best block
+	/// to telemetry with the given verbosity.
+	///
+	/// Only telemetry endpoints configured with at least this verbosity receive the
+	/// reports. Nothing is reported if unset.
+	#[structopt(long = "contracts-telemetry-verbosity", value_name = "VERBOSITY")]
+	pub contracts_telemetry_verbosity: Option<u8>,
+
+	/// Specify a port for an additional HTTP RPC server which serves the node specific
+	/// RPC methods with unsafe methods allowed.
+	///
+	/// These are `system_accountNextIndex`, `system_dryRun`, `payment_*`, `contracts_*`,
+	/// `balances_*`, `system_*` methods of the Canvas node (e.g. `system_decodeCall`),
+	/// `timestamp_nextExpected`, `utility_batchWeight` and `canvas_*`. Substrate's own
+	/// methods (e.g. `author_*`, `chain_*` and `state_*`) are not served, neither are
+	/// subscriptions, which need a WebSocket connection.
+	///
+	/// This server always listens on localhost, regardless of `--rpc-external`.
+	/// Together with `--rpc-methods Safe` it keeps unsafe methods off the public port.
+	#[structopt(long = "contracts-unsafe-rpc-port", value_name = "PORT")]
+	pub contracts_unsafe_rpc_port: Option<u16>,
+
+	/// The maximum number of transactions with a future nonce which the transaction
+	/// pool buffers until the missing nonces arrive.
+	///
+	/// dApps which submit transactions out of order need a larger buffer. It must be
+	/// positive and at most `--pool-limit`. Defaults to a tenth of `--pool-limit`.
+	#[structopt(long = "pool-future-limit", value_name = "COUNT")]
+	pub pool_future_limit: Option<usize>,
+}
+
+impl RunCmd {
+	/// The node specific part of the configuration.
+	pub fn canvas_config(&self) -> crate::service::CanvasConfig {
+		crate::service::CanvasConfig {
+			contracts_rpc_port: self.contracts_rpc_port,
+			log_contract_activity: self.log_contract_activity,
+			contracts_min_gas_limit: self.contracts_min_gas_limit,
+			contracts_telemetry_verbosity: self.contracts_telemetry_verbosity,
+			contracts_unsafe_rpc_port: self.contracts_unsafe_rpc_port,
+			pool_future_limit: self.pool_future_limit,
+		}
+	}
+}
+
 #[derive(Debug, StructOpt)]
 pub enum Subcommand {
 	/// Key management cli utilities
@@ -35,4 +106,29 @@ pub enum Subcommand {
 
 	/// Revert the chain to a previous state.
 	Revert(sc_cli::RevertCmd),
+
+	/// Export the state of a given block into a chain spec without any contracts storage.
+	///
+	/// The database is not changed. Restart the chain from the exported spec (after
+	/// `purge-chain` on every node) to reset its contracts.
+	PurgeContracts(crate::purge_contracts::PurgeContractsCmd),
+
+	/// Print the state root and hash of the genesis block of a chain spec.
+	GenesisHash(crate::genesis_hash::GenesisHashCmd),
+
+	/// Export the balances of all accounts at a given block as CSV.
+	ExportBalances(crate::export_balances::ExportBalancesCmd),
+
+	/// Check whether a contract would be accepted by the schedule of the chain.
+	CheckContract(crate::check_contract::CheckContractCmd),
+
+	/// Re-execute a range of blocks and check them against their state roots.
+	VerifyRange(crate::verify_range::VerifyRangeCmd),
+
+	/// Generate a node key and print the peer id derived from it.
+	GenerateNodeKey(crate::generate_node_key::GenerateNodeKeyCmd),
+
+	/// The custom benchmark subcommand benchmarking runtime code.
+	#[structopt(name = "benchmark", about = "Benchmark runtime code.")]
+	Benchmark(frame_benchmarking_cli::BenchmarkCmd),
 }
@@ -0,0 +1,261 @@
+//! Logging, telemetry and metrics of the contract activity in imported blocks.
+
+use std::sync::Arc;
+use canvas_runtime::{opaque::Block, Call, ContractsCall, Event, Hash, UncheckedExtrinsic, Weight};
+use codec::{Decode, Encode};
+use frame_system::{EventRecord, Phase};
+use futures::StreamExt;
+use log::{info, warn};
+use sc_client_api::{Backend, BlockBackend, BlockchainEvents, StorageProvider};
+use sc_telemetry::{telemetry, TelemetryHandle};
+use sp_consensus::BlockOrigin;
+use sp_core::storage::StorageKey;
+use sp_runtime::generic::BlockId;
+use substrate_prometheus_endpoint::{register, Gauge, PrometheusError, Registry, U64};
+
+/// Summary of the contract calls contained in a block.
+#[derive(Debug, Default, PartialEq)]
+struct ContractActivity {
+	instantiations: u32,
+	calls: u32,
+	/// The weight the contract calls consumed after refunds, which includes the gas
+	/// they used.
+	gas_used: Weight,
+}
+
+impl ContractActivity {
+	/// Reads the contract activity of block `hash`, `None` if its body is not available.
+	fn of_block<C, BE>(client: &C, hash: Hash) -> Option<Self> where
+		BE: Backend<Block>,
+		C: BlockBackend<Block> + StorageProvider<Block, BE>,
+	{
+		let extrinsics = client.block_body(&BlockId::Hash(hash)).ok()??;
+		Some(Self::from_extrinsics(&extrinsics, &events_of_block(client, hash)))
+	}
+
+	/// Summarizes the contract calls among `extrinsics`, whose consumed weight is looked
+	/// up in the `events` of their block.
+	fn from_extrinsics(
+		extrinsics: &[<Block as sp_runtime::traits::Block>::Extrinsic],
+		events: &[EventRecord<Event, Hash>],
+	) -> Self {
+		let mut activity = Self::default();
+		for (index, extrinsic) in extrinsics.iter().enumerate() {
+			let extrinsic = match UncheckedExtrinsic::decode(&mut &extrinsic.encode()[..]) {
+				Ok(extrinsic) => extrinsic,
+				Err(_) => continue,
+			};
+			match extrinsic.function {
+				Call::Contracts(ContractsCall::call(..)) => activity.calls += 1,
+				Call::Contracts(ContractsCall::instantiate_with_code(..)) |
+				Call::Contracts(ContractsCall::instantiate(..)) => activity.instantiations += 1,
+				_ => continue,
+			}
+			activity.gas_used = activity.gas_used
+				.saturating_add(consumed_weight(events, index as u32));
+		}
+		activity
+	}
+}
+
+/// The weight extrinsic `index` consumed according to the event which concluded it.
+fn consumed_weight(events: &[EventRecord<Event, Hash>], index: u32) -> Weight {
+	events.iter()
+		.find_map(|record| match (&record.phase, &record.event) {
+			(Phase::ApplyExtrinsic(i), Event::System(frame_system::Event::ExtrinsicSuccess(info))) |
+			(Phase::ApplyExtrinsic(i), Event::System(frame_system::Event::ExtrinsicFailed(_, info)))
+				if *i == index => Some(info.weight),
+			_ => None,
+		})
+		.unwrap_or(0)
+}
+
+/// Returns the events deposited in block `hash`.
+pub fn events_of_block<C, BE>(client: &C, hash: Hash) -> Vec<EventRecord<Event, Hash>> where
+	BE: Backend<Block>,
+	C: StorageProvider<Block, BE>,
+{
+	let key = StorageKey([sp_core::twox_128(b"System"), sp_core::twox_128(b"Events")].concat());
+	let events = match client.storage(&BlockId::Hash(hash), &key) {
+		Ok(Some(events)) => events,
+		Ok(None) => return Vec::new(),
+		Err(e) => {
+			warn!("Failed to read the events of block {}: {:?}", hash, e);
+			return Vec::new();
+		},
+	};
+	match Vec::<EventRecord<Event, Hash>>::decode(&mut &events.0[..]) {
+		Ok(events) => events,
+		Err(e) => {
+			warn!("Failed to decode the events of block {}: {:?}", hash, e);
+			Vec::new()
+		},
+	}
+}
+
+/// Logs a summary of the contract calls for every block authored by this node.
+///
+/// Only counts and the consumed gas are logged, never the call data. The work
+/// happens after the block is imported, so it does not delay authoring.
+pub async fn log_contract_activity<C, BE>(client: Arc<C>) where
+	BE: Backend<Block>,
+	C: BlockchainEvents<Block> + BlockBackend<Block> + StorageProvider<Block, BE>,
+{
+	let mut imports = client.import_notification_stream();
+
+	while let Some(notification) = imports.next().await {
+		if notification.origin != BlockOrigin::Own {
+			continue;
+		}
+
+		let activity = match ContractActivity::of_block(&*client, notification.hash) {
+			Some(activity) => activity,
+			None => continue,
+		};
+		info!(
+			target: "contracts",
+			"📜 Authored block #{} ({}) with {} contract instantiations and {} contract calls, gas used {}",
+			notification.header.number,
+			notification.hash,
+			activity.instantiations,
+			activity.calls,
+			activity.gas_used,
+		);
+	}
+}
+
+/// Reports the contract activity of every new best block to telemetry.
+///
+/// The messages are sent with the given `verbosity`, so they only reach telemetry
+/// endpoints which are configured with at least this verbosity.
+pub async fn report_contract_activity<C, BE>(client: Arc<C>, telemetry: TelemetryHandle, verbosity: u8)
+where
+	BE: Backend<Block>,
+	C: BlockchainEvents<Block> + BlockBackend<Block> + StorageProvider<Block, BE>,
+{
+	let mut imports = client.import_notification_stream();
+
+	while let Some(notification) = imports.next().await {
+		if !notification.is_new_best {
+			continue;
+		}
+
+		let activity = match ContractActivity::of_block(&*client, notification.hash) {
+			Some(activity) => activity,
+			None => continue,
+		};
+		telemetry!(
+			telemetry;
+			verbosity;
+			"canvas.contracts";
+			"height" => notification.header.number,
+			"best" => ?notification.hash,
+			"instantiations" => activity.instantiations,
+			"calls" => activity.calls,
+			"gas_used" => activity.gas_used,
+		);
+	}
+}
+
+/// Prometheus gauges of the contract activity in the best block.
+pub struct ContractActivityMetrics {
+	instantiations: Gauge<U64>,
+	calls: Gauge<U64>,
+	gas_used: Gauge<U64>,
+}
+
+impl ContractActivityMetrics {
+	/// Register the gauges in `registry`.
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			instantiations: register(
+				Gauge::new(
+					"canvas_contract_instantiations",
+					"Number of contract instantiations in the best block",
+				)?,
+				registry,
+			)?,
+			calls: register(
+				Gauge::new("canvas_contract_calls", "Number of contract calls in the best block")?,
+				registry,
+			)?,
+			gas_used: register(
+				Gauge::new(
+					"canvas_contract_gas_used",
+					"Weight consumed by the contract calls in the best block",
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
+
+/// Exports the contract activity of every new best block to Prometheus.
+pub async fn export_contract_activity<C, BE>(client: Arc<C>, metrics: ContractActivityMetrics)
+where
+	BE: Backend<Block>,
+	C: BlockchainEvents<Block> + BlockBackend<Block> + StorageProvider<Block, BE>,
+{
+	let mut imports = client.import_notification_stream();
+
+	while let Some(notification) = imports.next().await {
+		if !notification.is_new_best {
+			continue;
+		}
+
+		let activity = match ContractActivity::of_block(&*client, notification.hash) {
+			Some(activity) => activity,
+			None => continue,
+		};
+		metrics.instantiations.set(activity.instantiations.into());
+		metrics.calls.set(activity.calls.into());
+		metrics.gas_used.set(activity.gas_used);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_chain;
+	use canvas_runtime::{Address, BalancesCall, DOLLARS};
+	use sp_blockchain::HeaderBackend;
+	use sp_keyring::AccountKeyring;
+
+	#[test]
+	fn the_consumed_gas_of_the_contract_calls_of_a_block_is_summed() {
+		let client = test_chain::new_client();
+		let (contract, _) = test_chain::instantiate(
+			&client,
+			AccountKeyring::Alice,
+			test_chain::DUMMY_CONTRACT,
+			100 * DOLLARS,
+		);
+		let instantiated = ContractActivity::of_block(&*client, client.info().best_hash).unwrap();
+		assert_eq!((instantiated.instantiations, instantiated.calls), (1, 0));
+		assert!(instantiated.gas_used > 0);
+
+		let call = |signer| {
+			let call = ContractsCall::call(Address::Id(contract.clone()), 0, test_chain::GAS_LIMIT, vec![]);
+			test_chain::sign(&client, signer, Call::Contracts(call))
+		};
+		let transfer = test_chain::sign(
+			&client,
+			AccountKeyring::Charlie,
+			Call::Balances(BalancesCall::transfer(Address::Id(AccountKeyring::Dave.to_account_id()), DOLLARS)),
+		);
+		let hash = test_chain::import_block(
+			&client,
+			vec![call(AccountKeyring::Alice), transfer, call(AccountKeyring::Bob)],
+		);
+
+		let activity = ContractActivity::of_block(&*client, hash).unwrap();
+		assert_eq!((activity.instantiations, activity.calls), (0, 2));
+		let events = events_of_block(&*client, hash);
+		// The timestamp is extrinsic 0, the contract calls are 1 and 3.
+		let consumed = consumed_weight(&events, 1) + consumed_weight(&events, 3);
+		assert_eq!(activity.gas_used, consumed);
+		// Most of the gas limit is refunded, as the contract does nothing.
+		assert!(consumed > 0 && consumed < 2 * test_chain::GAS_LIMIT);
+		assert!(consumed_weight(&events, 2) > 0);
+	}
+}
@@ -0,0 +1,199 @@
+//! The `export-balances` subcommand.
+//!
+//! Writes the balances of all accounts at a given block as CSV, e.g. to plan
+//! an airdrop. Every row contains the SS58 address together with the free and
+//! the reserved balance of the account.
+
+use std::{fmt::Debug, fs::File, io::{self, Write}, path::PathBuf, str::FromStr, sync::Arc};
+use canvas_runtime::{AccountId, Balance, ExistentialDeposit, Index};
+use codec::Decode;
+use log::info;
+use sc_cli::{BlockNumberOrHash, CliConfiguration, DatabaseParams, PruningParams, SharedParams};
+use sc_client_api::{StorageProvider, UsageProvider};
+use sp_core::{crypto::Ss58Codec, storage::StorageKey};
+use sp_runtime::{generic::BlockId, traits::{Block as BlockT, Get, Header as HeaderT}};
+use structopt::StructOpt;
+
+type AccountInfo = frame_system::AccountInfo<Index, pallet_balances::AccountData<Balance>>;
+
+/// The `export-balances` command used to snapshot all account balances.
+#[derive(Debug, StructOpt)]
+pub struct ExportBalancesCmd {
+	/// Block hash or number of the state to export. Defaults to the best block.
+	#[structopt(long, value_name = "HASH or NUMBER")]
+	pub at: Option<BlockNumberOrHash>,
+
+	/// Output file name or stdout if unspecified.
+	#[structopt(parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	/// Skip accounts whose total balance is below the existential deposit.
+	#[structopt(long)]
+	pub skip_dust: bool,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl ExportBalancesCmd {
+	/// Run the export-balances command
+	pub async fn run<B, BA, C>(&self, client: Arc<C>) -> sc_cli::Result<()>
+	where
+		B: BlockT,
+		<B::Hash as FromStr>::Err: Debug,
+		<<B::Header as HeaderT>::Number as FromStr>::Err: Debug,
+		BA: sc_client_api::backend::Backend<B>,
+		C: UsageProvider<B> + StorageProvider<B, BA>,
+	{
+		let block_id = match &self.at {
+			Some(at) => at.parse::<B>()?,
+			None => BlockId::Hash(client.usage_info().chain.best_hash),
+		};
+
+		let mut output: Box<dyn Write> = match &self.output {
+			Some(path) => Box::new(File::create(path)?),
+			None => Box::new(io::stdout()),
+		};
+
+		info!("Exporting balances at {}...", block_id);
+		let accounts = client.storage_pairs(&block_id, &StorageKey(account_prefix()))
+			.map_err(|e| format!("Failed to read the accounts: {}", e))?;
+
+		let exported = write_balances(
+			&mut output,
+			accounts.into_iter().map(|(key, value)| (key.0, value.0)),
+			self.skip_dust,
+		)?;
+
+		info!("Exported the balances of {} accounts.", exported);
+		Ok(())
+	}
+}
+
+/// The storage prefix of `frame_system::Account`.
+fn account_prefix() -> Vec<u8> {
+	[sp_core::twox_128(b"System"), sp_core::twox_128(b"Account")].concat()
+}
+
+/// Writes the `frame_system::Account` storage `entries` as CSV and returns the number of rows.
+///
+/// Entries which cannot be decoded are skipped, as are dust accounts if `skip_dust` is set.
+fn write_balances(
+	output: &mut dyn Write,
+	entries: impl IntoIterator<Item = (Vec<u8>, Vec<u8>)>,
+	skip_dust: bool,
+) -> io::Result<usize> {
+	let prefix_len = account_prefix().len();
+	writeln!(output, "address,free,reserved")?;
+	let mut exported = 0;
+	for (key, value) in entries {
+		// The key is the prefix, followed by the `Blake2_128Concat` hashed account id.
+		let account = match key.get(prefix_len + 16..)
+			.and_then(|mut raw| AccountId::decode(&mut raw).ok())
+		{
+			Some(account) => account,
+			None => continue,
+		};
+		let info = match AccountInfo::decode(&mut &value[..]) {
+			Ok(info) => info,
+			Err(_) => continue,
+		};
+		let data = info.data;
+		if skip_dust && data.free.saturating_add(data.reserved) < ExistentialDeposit::get() {
+			continue;
+		}
+		writeln!(output, "{},{},{}", account.to_ss58check(), data.free, data.reserved)?;
+		exported += 1;
+	}
+	Ok(exported)
+}
+
+impl CliConfiguration for ExportBalancesCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+
+	const ALICE: [u8; 32] = [1; 32];
+	const BOB: [u8; 32] = [2; 32];
+
+	fn entry(account: &AccountId, free: Balance, reserved: Balance) -> (Vec<u8>, Vec<u8>) {
+		let encoded = account.encode();
+		let key = [account_prefix(), sp_core::blake2_128(&encoded).to_vec(), encoded].concat();
+		let mut info = AccountInfo::default();
+		info.data.free = free;
+		info.data.reserved = reserved;
+		(key, info.encode())
+	}
+
+	fn state() -> Vec<(Vec<u8>, Vec<u8>)> {
+		vec![
+			entry(&ALICE.into(), 1_000_000_000_000, 42),
+			entry(&BOB.into(), ExistentialDeposit::get() - 1, 0),
+			(account_prefix(), b"not an account".to_vec()),
+		]
+	}
+
+	/// Parses the rows written by `write_balances` back into accounts and balances.
+	fn parse(csv: &[u8]) -> Vec<(AccountId, Balance, Balance)> {
+		let csv = std::str::from_utf8(csv).unwrap();
+		let mut lines = csv.lines();
+		assert_eq!(lines.next(), Some("address,free,reserved"));
+		lines
+			.map(|line| {
+				let columns: Vec<_> = line.split(',').collect();
+				assert_eq!(columns.len(), 3);
+				(
+					AccountId::from_ss58check(columns[0]).unwrap(),
+					columns[1].parse().unwrap(),
+					columns[2].parse().unwrap(),
+				)
+			})
+			.collect()
+	}
+
+	#[test]
+	fn balances_round_trip_through_csv() {
+		let mut csv = Vec::new();
+		assert_eq!(write_balances(&mut csv, state(), false).unwrap(), 2);
+		assert_eq!(
+			parse(&csv),
+			vec![
+				(AccountId::from(ALICE), 1_000_000_000_000, 42),
+				(AccountId::from(BOB), ExistentialDeposit::get() - 1, 0),
+			],
+		);
+	}
+
+	#[test]
+	fn dust_accounts_are_skipped_on_request() {
+		let mut csv = Vec::new();
+		assert_eq!(write_balances(&mut csv, state(), true).unwrap(), 1);
+		assert_eq!(
+			parse(&csv),
+			vec![(AccountId::from(ALICE), 1_000_000_000_000, 42)],
+		);
+	}
+}
@@ -0,0 +1,118 @@
+//! The `generate-node-key` subcommand.
+//!
+//! Generates the ed25519 key which identifies a node on the network and prints the
+//! peer id derived from it, e.g. to put it into the `--bootnodes` of other nodes.
+//! Unlike `key generate-node-key` it prints the peer id to stdout and can derive the
+//! key from a seed, so that test networks can be set up with known peer ids.
+
+use std::{fs, io::Write, path::{Path, PathBuf}};
+use sc_network::config::identity::{ed25519, PublicKey};
+use structopt::StructOpt;
+
+/// The `generate-node-key` command used to generate a node key and its peer id.
+#[derive(Debug, StructOpt)]
+pub struct GenerateNodeKeyCmd {
+	/// File to write the hex encoded secret key to, as read by `--node-key-file`.
+	#[structopt(long, value_name = "FILE", parse(from_os_str))]
+	pub file: Option<PathBuf>,
+
+	/// Print the secret key instead of writing it to `--file`.
+	#[structopt(long)]
+	pub dry_run: bool,
+
+	/// Derive the key from the blake2 hash of this seed instead of generating a random one.
+	///
+	/// Anyone knowing the seed knows the key, only use it for test networks.
+	#[structopt(long, value_name = "SEED")]
+	pub seed: Option<String>,
+}
+
+impl GenerateNodeKeyCmd {
+	/// Run the generate-node-key command
+	pub fn run(&self) -> sc_cli::Result<()> {
+		let keypair = self.keypair()?;
+		let secret = hex::encode(keypair.secret().as_ref());
+		let peer_id = PublicKey::Ed25519(keypair.public()).into_peer_id();
+
+		if self.dry_run {
+			println!("Secret key: {}", secret);
+		} else {
+			let file = self.file.as_ref().ok_or("Either --file or --dry-run is required")?;
+			if file.exists() {
+				return Err(format!("{} already exists, not overwriting it", file.display()).into());
+			}
+			write_secret(file, &secret)?;
+			println!("Secret key written to {}", file.display());
+		}
+		println!("Peer id:    {}", peer_id);
+		Ok(())
+	}
+
+	/// The key derived from `--seed`, or a random one without a seed.
+	fn keypair(&self) -> sc_cli::Result<ed25519::Keypair> {
+		match &self.seed {
+			Some(seed) => {
+				let mut secret = sp_core::blake2_256(seed.as_bytes());
+				let secret = ed25519::SecretKey::from_bytes(&mut secret)
+					.map_err(|e| format!("Invalid node key derived from the seed: {}", e))?;
+				Ok(ed25519::Keypair::from(secret))
+			},
+			None => Ok(ed25519::Keypair::generate()),
+		}
+	}
+}
+
+/// Writes `secret` to the new `file`, which only its owner may read on unix.
+fn write_secret(file: &Path, secret: &str) -> std::io::Result<()> {
+	let mut options = fs::OpenOptions::new();
+	options.write(true).create_new(true);
+	#[cfg(unix)]
+	std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+	options.open(file)?.write_all(secret.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn key_and_peer_id(args: &[&str]) -> (String, String) {
+		let cmd = GenerateNodeKeyCmd::from_iter(
+			std::iter::once("generate-node-key").chain(args.iter().copied()),
+		);
+		let keypair = cmd.keypair().unwrap();
+		let peer_id = PublicKey::Ed25519(keypair.public()).into_peer_id();
+		(hex::encode(keypair.secret().as_ref()), peer_id.to_base58())
+	}
+
+	#[test]
+	fn the_same_seed_gives_the_same_key_and_peer_id() {
+		let alice = key_and_peer_id(&["--dry-run", "--seed", "alice"]);
+		assert_eq!(key_and_peer_id(&["--dry-run", "--seed", "alice"]), alice);
+		assert_eq!(alice.0, hex::encode(sp_core::blake2_256(b"alice")));
+
+		let bob = key_and_peer_id(&["--dry-run", "--seed", "bob"]);
+		assert_ne!(bob.0, alice.0);
+		assert_ne!(bob.1, alice.1);
+	}
+
+	#[test]
+	fn keys_without_a_seed_are_random() {
+		assert_ne!(key_and_peer_id(&["--dry-run"]), key_and_peer_id(&["--dry-run"]));
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn the_secret_is_only_readable_by_its_owner() {
+		use std::os::unix::fs::PermissionsExt;
+
+		let file = std::env::temp_dir().join(format!("canvas-node-key-{}", std::process::id()));
+		write_secret(&file, "secret").unwrap();
+		let mode = fs::metadata(&file).unwrap().permissions().mode();
+		let secret = fs::read_to_string(&file).unwrap();
+		assert!(write_secret(&file, "other").is_err());
+		fs::remove_file(&file).unwrap();
+
+		assert_eq!(mode & 0o777, 0o600);
+		assert_eq!(secret, "secret");
+	}
+}
@@ -0,0 +1,68 @@
+//! The `genesis-hash` subcommand.
+//!
+//! Builds the genesis storage of a chain spec and prints its state root together
+//! with the hash of the resulting genesis block, without opening a database or
+//! starting the node. CI uses this to detect accidental changes to the genesis.
+
+use codec::Decode;
+use sc_cli::{ChainSpec, CliConfiguration, SharedParams};
+use sp_runtime::traits::{Block as BlockT, Hash as HashT, Header as HeaderT, Zero};
+use sp_state_machine::{BasicExternalities, Externalities};
+use structopt::StructOpt;
+
+/// The `genesis-hash` command used to print the state root of the genesis.
+#[derive(Debug, StructOpt)]
+pub struct GenesisHashCmd {
+	/// Exit with an error if the genesis state root differs from this one.
+	#[structopt(long, value_name = "HASH")]
+	pub expected: Option<String>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl GenesisHashCmd {
+	/// Run the genesis-hash command
+	pub fn run<B>(&self, spec: Box<dyn ChainSpec>) -> sc_cli::Result<()>
+	where
+		B: BlockT,
+	{
+		let storage = spec.as_storage_builder().build_storage()?;
+		// Child trie roots are inserted into the top trie before its root is
+		// computed, just like the client does when it writes the genesis state.
+		let state_root = BasicExternalities::new(storage).storage_root();
+		let state_root = <B::Hash as Decode>::decode(&mut &state_root[..])
+			.map_err(|e| format!("Invalid state root: {}", e))?;
+
+		let extrinsics_root = <<B::Header as HeaderT>::Hashing as HashT>::trie_root(Vec::new());
+		let genesis = <B::Header as HeaderT>::new(
+			Zero::zero(),
+			extrinsics_root,
+			state_root,
+			Default::default(),
+			Default::default(),
+		);
+
+		println!("State root:   {:?}", state_root);
+		println!("Genesis hash: {:?}", genesis.hash());
+
+		if let Some(expected) = &self.expected {
+			let expected = expected.trim_start_matches("0x");
+			if hex::encode(state_root.as_ref()) != expected.to_lowercase() {
+				return Err(format!(
+					"Genesis state root {:?} does not match the expected 0x{}",
+					state_root,
+					expected,
+				).into());
+			}
+		}
+		Ok(())
+	}
+}
+
+impl CliConfiguration for GenesisHashCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+}
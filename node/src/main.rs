@@ -7,6 +7,15 @@ mod service;
 mod cli;
 mod command;
 mod rpc;
+mod contract_activity;
+mod purge_contracts;
+mod genesis_hash;
+mod export_balances;
+mod check_contract;
+mod verify_range;
+mod generate_node_key;
+#[cfg(test)]
+mod test_chain;
 
 fn main() -> sc_cli::Result<()> {
 	command::run()
@@ -0,0 +1,258 @@
+//! The `purge-contracts` subcommand.
+//!
+//! Exports the state of a block as a raw chain spec with all storage of the
+//! contracts pallet removed. Starting a chain from this spec resets every contract
+//! (including the deletion queue) while balances and accounts are preserved.
+//! The storage which the Canvas pallets keep per contract is removed as well.
+//! The storage version of the contracts pallet is kept, so that the next runtime
+//! upgrade does not migrate the empty storage again.
+//!
+//! The database of the node is not changed. Removing storage from it in place would
+//! not match the state roots in the headers of the existing blocks, so the other
+//! nodes would reject every block built on top of it. Instead the exported spec is the
+//! genesis of a new chain. To reset a testnet its operators:
+//!
+//! 1. Export the spec on one node: `canvas purge-contracts --chain <spec> -y > reset.json`.
+//! 2. Stop every node and remove its database with `canvas purge-chain`.
+//! 3. Start every node with `--chain reset.json`.
+
+use std::{fmt::Debug, io::{self, Write}, str::FromStr, sync::Arc};
+use log::info;
+use sc_cli::{
+	BlockNumberOrHash, ChainSpec, CliConfiguration, DatabaseParams, PruningParams, SharedParams,
+};
+use sc_client_api::{StorageProvider, UsageProvider};
+use sp_core::storage::Storage;
+use sp_runtime::{generic::BlockId, traits::{Block as BlockT, Header as HeaderT}};
+use structopt::StructOpt;
+
+/// The name of the contracts pallet in `construct_runtime!`.
+const CONTRACTS_PALLET: &[u8] = b"Contracts";
+
+/// The item under the prefix of a pallet which stores its version, see
+/// `frame_support::traits::PALLET_VERSION_STORAGE_KEY_POSTFIX`.
+const PALLET_VERSION: &[u8] = b":__PALLET_VERSION__:";
+
+/// The storage items of other pallets which refer to contracts, as `(pallet, item)`.
+///
+/// Storage which is configured by the chain's operators, like the code whitelist,
+/// is kept.
+const CONTRACT_STORAGE: &[(&[u8], &[u8])] = &[
+	(b"ContractsGuard", b"OwnedContracts"),
+	(b"ContractsMetrics", b"CallCount"),
+	(b"ContractsMetrics", b"Provenance"),
+	(b"ContractsMetrics", b"RecentEvents"),
+	(b"ContractsMetrics", b"ContractStorage"),
+	(b"ContractsMetrics", b"TotalStorage"),
+];
+
+/// The `purge-contracts` command used to reset all contracts.
+#[derive(Debug, StructOpt)]
+pub struct PurgeContractsCmd {
+	/// Block hash or number of the state to purge. Defaults to the best block.
+	#[structopt(long, value_name = "HASH or NUMBER")]
+	pub at: Option<BlockNumberOrHash>,
+
+	/// Only list the storage which would be removed.
+	#[structopt(long)]
+	pub dry_run: bool,
+
+	/// Skip interactive prompt by answering yes automatically.
+	#[structopt(short = "y")]
+	pub yes: bool,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl PurgeContractsCmd {
+	/// Run the purge-contracts command
+	pub async fn run<B, BA, C>(
+		&self,
+		client: Arc<C>,
+		mut input_spec: Box<dyn ChainSpec>,
+	) -> sc_cli::Result<()>
+	where
+		B: BlockT,
+		<B::Hash as FromStr>::Err: Debug,
+		<<B::Header as HeaderT>::Number as FromStr>::Err: Debug,
+		BA: sc_client_api::backend::Backend<B>,
+		C: UsageProvider<B> + StorageProvider<B, BA>,
+	{
+		let block_id = match &self.at {
+			Some(at) => at.parse::<B>()?,
+			None => BlockId::Hash(client.usage_info().chain.best_hash),
+		};
+
+		info!("Exporting raw state at {}...", block_id);
+		let mut raw_state = sc_service::chain_ops::export_raw_state(client, &block_id)?;
+
+		let contracts_keys = contracts_keys(&raw_state);
+		// Contracts are the only users of child tries in this runtime.
+		let contracts_tries = raw_state.children_default.len();
+
+		if self.dry_run {
+			for key in &contracts_keys {
+				println!("0x{}", hex::encode(key));
+			}
+			println!(
+				"{} top level storage entries and {} contract child tries would be removed.",
+				contracts_keys.len(),
+				contracts_tries,
+			);
+			return Ok(());
+		}
+
+		if !self.yes {
+			eprint!(
+				"Remove {} top level storage entries and {} contract child tries from the \
+				exported state? [y/N]: ",
+				contracts_keys.len(),
+				contracts_tries,
+			);
+			io::stderr().flush().expect("failed to flush stderr");
+
+			let mut input = String::new();
+			io::stdin().read_line(&mut input)?;
+			let input = input.trim();
+
+			match input.chars().next() {
+				Some('y') | Some('Y') => {},
+				_ => {
+					eprintln!("Aborted");
+					return Ok(());
+				},
+			}
+		}
+
+		purge(&mut raw_state, &contracts_keys);
+		input_spec.set_storage(raw_state);
+
+		info!("Generating new chain spec...");
+		let json = sc_service::chain_ops::build_spec(&*input_spec, true)?;
+		if io::stdout().write_all(json.as_bytes()).is_err() {
+			let _ = io::stderr().write_all(b"Error writing to stdout\n");
+		}
+		Ok(())
+	}
+}
+
+/// Returns the keys of the top level storage which belongs to contracts.
+fn contracts_keys(storage: &Storage) -> Vec<Vec<u8>> {
+	let mut prefixes = vec![sp_core::twox_128(CONTRACTS_PALLET).to_vec()];
+	prefixes.extend(CONTRACT_STORAGE.iter().map(|(pallet, item)| {
+		[sp_core::twox_128(pallet), sp_core::twox_128(item)].concat()
+	}));
+	let version = [sp_core::twox_128(CONTRACTS_PALLET), sp_core::twox_128(PALLET_VERSION)].concat();
+
+	storage.top.keys()
+		.filter(|key| prefixes.iter().any(|prefix| key.starts_with(prefix)))
+		.filter(|key| **key != version)
+		.cloned()
+		.collect()
+}
+
+/// Removes the given top level keys and all child tries from `storage`.
+fn purge(storage: &mut Storage, keys: &[Vec<u8>]) {
+	for key in keys {
+		storage.top.remove(key);
+	}
+	storage.children_default.clear();
+}
+
+impl CliConfiguration for PurgeContractsCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::storage::{ChildInfo, StorageChild};
+	use sp_runtime::traits::BlakeTwo256;
+	use sp_state_machine::{Backend, InMemoryBackend};
+
+	fn key(pallet: &[u8], item: &[u8], suffix: &[u8]) -> Vec<u8> {
+		[&sp_core::twox_128(pallet)[..], &sp_core::twox_128(item)[..], suffix].concat()
+	}
+
+	fn state() -> Storage {
+		let child_info = ChildInfo::new_default(b"contract trie");
+		let entries: &[(&[u8], &[u8])] = &[
+			(b"Contracts", b"ContractInfoOf"),
+			(b"Contracts", b"DeletionQueue"),
+			(b"ContractsGuard", b"OwnedContracts"),
+			(b"ContractsGuard", b"CodeWhitelist"),
+			(b"ContractsMetrics", b"CallCount"),
+			(b"ContractsMetrics", b"Provenance"),
+			(b"ContractsMetrics", b"RecentEvents"),
+			(b"ContractsMetrics", b"ContractStorage"),
+			(b"ContractsMetrics", b"TotalStorage"),
+			(b"System", b"Account"),
+			(b"Balances", b"TotalIssuance"),
+		];
+		let mut storage = Storage::default();
+		for (pallet, item) in entries {
+			storage.top.insert(key(pallet, item, b"key"), b"value".to_vec());
+		}
+		storage.top.insert(key(CONTRACTS_PALLET, PALLET_VERSION, b""), b"version".to_vec());
+		storage.children_default.insert(
+			child_info.storage_key().to_vec(),
+			StorageChild {
+				data: vec![(b"key".to_vec(), b"value".to_vec())].into_iter().collect(),
+				child_info,
+			},
+		);
+		storage
+	}
+
+	#[test]
+	fn only_contracts_storage_is_purged() {
+		let mut storage = state();
+		let keys = contracts_keys(&storage);
+		assert_eq!(keys.len(), 8);
+
+		purge(&mut storage, &keys);
+
+		let backend = InMemoryBackend::<BlakeTwo256>::from(storage);
+		let exists = |pallet: &[u8], item: &[u8]| {
+			backend.storage(&key(pallet, item, b"key")).unwrap().is_some()
+		};
+		assert!(!exists(b"Contracts", b"ContractInfoOf"));
+		assert!(!exists(b"Contracts", b"DeletionQueue"));
+		assert!(!exists(b"ContractsGuard", b"OwnedContracts"));
+		assert!(!exists(b"ContractsMetrics", b"CallCount"));
+		assert!(!exists(b"ContractsMetrics", b"Provenance"));
+		assert!(!exists(b"ContractsMetrics", b"RecentEvents"));
+		assert!(!exists(b"ContractsMetrics", b"ContractStorage"));
+		assert!(!exists(b"ContractsMetrics", b"TotalStorage"));
+		assert!(exists(b"ContractsGuard", b"CodeWhitelist"));
+		assert_eq!(
+			backend.storage(&key(CONTRACTS_PALLET, PALLET_VERSION, b"")).unwrap(),
+			Some(b"version".to_vec()),
+		);
+		assert!(exists(b"System", b"Account"));
+		assert!(exists(b"Balances", b"TotalIssuance"));
+
+		let child_info = ChildInfo::new_default(b"contract trie");
+		assert_eq!(backend.child_storage(&child_info, b"key").unwrap(), None);
+	}
+}
@@ -0,0 +1,254 @@
+//! Canvas specific balances RPC methods.
+
+use std::{convert::TryFrom, marker::PhantomData, sync::Arc};
+
+use canvas_runtime::runtime_api::{
+	CanvasBalancesApi as CanvasBalancesRuntimeApi, ReserveIdentifier,
+};
+use codec::Codec;
+use jsonrpc_core::{Error, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_rpc::number::NumberOrHex;
+use sp_runtime::traits::Block as BlockT;
+
+use super::{at_or_best, decode_number, runtime_error_into_rpc_err, DenyUnsafe, INVALID_PARAMS};
+
+/// An amount of balance which is reserved under a named identifier.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamedReserve {
+	/// The identifier of the reserve.
+	pub id: Bytes,
+	/// The reserved amount.
+	///
+	/// Balances do not fit into a JSON number, hence this is a hex string if it exceeds `u64`.
+	pub amount: NumberOrHex,
+}
+
+/// An amount of free balance which is locked under a named identifier.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamedLock {
+	/// The identifier of the lock.
+	pub id: Bytes,
+	/// The locked amount.
+	pub amount: NumberOrHex,
+}
+
+/// The balance of an account and what restricts spending it.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountLedger {
+	/// The free balance.
+	pub free: NumberOrHex,
+	/// The reserved balance, the sum of the named and the anonymous reserves.
+	pub reserved: NumberOrHex,
+	/// The part of `free` which can not be spent other than for fees.
+	pub misc_frozen: NumberOrHex,
+	/// The part of `free` which can not be spent for fees.
+	pub fee_frozen: NumberOrHex,
+	/// The locks on the free balance.
+	pub locks: Vec<NamedLock>,
+	/// The named reserves.
+	pub reserves: Vec<NamedReserve>,
+}
+
+/// An account holding a named reserve.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReserveHolder<AccountId> {
+	/// The account holding the reserve.
+	pub account: AccountId,
+	/// The reserved amount.
+	pub amount: NumberOrHex,
+}
+
+/// Canvas specific balances RPC methods.
+#[rpc]
+pub trait CanvasBalancesApi<BlockHash, AccountId, Balance> {
+	/// Returns the named reserves of `account`.
+	///
+	/// This shows which parts of the reserved balance of an account are held
+	/// for what purpose.
+	#[rpc(name = "balances_reservesOf")]
+	fn reserves_of(
+		&self,
+		account: AccountId,
+		at: Option<BlockHash>,
+	) -> Result<Vec<NamedReserve>>;
+
+	/// Returns the accounts which hold a reserve under the 8 byte `identifier`.
+	///
+	/// Skips the first `offset` accounts and returns at most `limit` (up to 1024)
+	/// accounts per request. This is an unsafe method, the runtime scans the reserves
+	/// of all accounts.
+	#[rpc(name = "balances_accountsWithReserve")]
+	fn accounts_with_reserve(
+		&self,
+		identifier: Bytes,
+		offset: u32,
+		limit: u32,
+		at: Option<BlockHash>,
+	) -> Result<Vec<ReserveHolder<AccountId>>>;
+
+	/// Returns the free and reserved balance of `account`, how much of it is frozen and
+	/// the locks and named reserves causing this.
+	///
+	/// `pallet_balances` has no holds or freezes yet; locks freeze the free balance and
+	/// named reserves are the closest equivalent of holds.
+	#[rpc(name = "balances_accountLedger")]
+	fn account_ledger(
+		&self,
+		account: AccountId,
+		at: Option<BlockHash>,
+	) -> Result<AccountLedger>;
+
+	/// Returns the fee of a `Balances::transfer` of `value`.
+	///
+	/// If `keep_alive` is `true` the fee of `transfer_keep_alive` is returned instead.
+	/// Wallets can show the fee without building and signing an extrinsic first.
+	#[rpc(name = "payment_transferFee")]
+	fn transfer_fee(
+		&self,
+		value: NumberOrHex,
+		keep_alive: bool,
+		at: Option<BlockHash>,
+	) -> Result<NumberOrHex>;
+}
+
+/// An implementation of Canvas specific balances RPC methods.
+pub struct CanvasBalances<C, B> {
+	client: Arc<C>,
+	deny_unsafe: DenyUnsafe,
+	_marker: PhantomData<B>,
+}
+
+impl<C, B> CanvasBalances<C, B> {
+	/// Create new `CanvasBalances` with the given reference to the client.
+	pub fn new(client: Arc<C>, deny_unsafe: DenyUnsafe) -> Self {
+		Self { client, deny_unsafe, _marker: Default::default() }
+	}
+}
+
+impl<C, Block, AccountId, Balance>
+	CanvasBalancesApi<<Block as BlockT>::Hash, AccountId, Balance>
+	for CanvasBalances<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static,
+	C: ProvideRuntimeApi<Block>,
+	C: HeaderBackend<Block>,
+	C::Api: CanvasBalancesRuntimeApi<Block, AccountId, Balance>,
+	AccountId: Codec,
+	Balance: Codec + TryFrom<NumberOrHex> + Into<NumberOrHex>,
+{
+	fn reserves_of(
+		&self,
+		account: AccountId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<NamedReserve>> {
+		let api = self.client.runtime_api();
+		let at = at_or_best(&*self.client, at);
+
+		let reserves = api.reserves_of(&at, account).map_err(runtime_error_into_rpc_err)?;
+
+		Ok(reserves
+			.into_iter()
+			.map(|(id, amount)| NamedReserve { id: id.to_vec().into(), amount: amount.into() })
+			.collect())
+	}
+
+	fn accounts_with_reserve(
+		&self,
+		identifier: Bytes,
+		offset: u32,
+		limit: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<ReserveHolder<AccountId>>> {
+		self.deny_unsafe.check_if_safe()?;
+
+		let api = self.client.runtime_api();
+		let at = at_or_best(&*self.client, at);
+
+		let id = ReserveIdentifier::try_from(&identifier[..]).map_err(|_| Error {
+			code: ErrorCode::ServerError(INVALID_PARAMS),
+			message: format!("The identifier must be 8 bytes, got {}", identifier.len()),
+			data: None,
+		})?;
+
+		let holders = api.accounts_with_reserve(&at, id, offset, limit)
+			.map_err(runtime_error_into_rpc_err)?;
+		Ok(holders
+			.into_iter()
+			.map(|(account, amount)| ReserveHolder { account, amount: amount.into() })
+			.collect())
+	}
+
+	fn account_ledger(
+		&self,
+		account: AccountId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<AccountLedger> {
+		let api = self.client.runtime_api();
+		let at = at_or_best(&*self.client, at);
+
+		let ledger = api.account_ledger(&at, account).map_err(runtime_error_into_rpc_err)?;
+
+		Ok(AccountLedger {
+			free: ledger.free.into(),
+			reserved: ledger.reserved.into(),
+			misc_frozen: ledger.misc_frozen.into(),
+			fee_frozen: ledger.fee_frozen.into(),
+			locks: ledger.locks
+				.into_iter()
+				.map(|(id, amount)| NamedLock { id: id.to_vec().into(), amount: amount.into() })
+				.collect(),
+			reserves: ledger.reserves
+				.into_iter()
+				.map(|(id, amount)| NamedReserve { id: id.to_vec().into(), amount: amount.into() })
+				.collect(),
+		})
+	}
+
+	fn transfer_fee(
+		&self,
+		value: NumberOrHex,
+		keep_alive: bool,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<NumberOrHex> {
+		let api = self.client.runtime_api();
+		let at = at_or_best(&*self.client, at);
+
+		let value: Balance = decode_number(value, "balance")?;
+		api.transfer_fee(&at, value, keep_alive)
+			.map(Into::into)
+			.map_err(runtime_error_into_rpc_err)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn reserves_above_u64_are_serialized_as_hex() {
+		let reserve = NamedReserve {
+			id: b"feestake".to_vec().into(),
+			amount: (u64::MAX as u128 + 1).into(),
+		};
+		assert_eq!(
+			serde_json::to_string(&reserve).unwrap(),
+			r#"{"id":"0x6665657374616b65","amount":"0x10000000000000000"}"#,
+		);
+
+		let reserve = NamedReserve { id: b"feestake".to_vec().into(), amount: 500u128.into() };
+		assert_eq!(
+			serde_json::to_string(&reserve).unwrap(),
+			r#"{"id":"0x6665657374616b65","amount":500}"#,
+		);
+	}
+}
@@ -0,0 +1,667 @@
+//! Canvas specific contracts RPC methods.
+//!
+//! These complement the methods of `pallet_contracts_rpc` with conveniences
+//! that are useful when working with ink! contracts.
+
+use std::{convert::TryFrom, marker::PhantomData, sync::Arc};
+
+use canvas_runtime::{
+	runtime_api::{
+		CanvasContractsApi as CanvasContractsRuntimeApi, DeletionQueue, StorageUsage,
+	},
+	BlockNumber,
+};
+use codec::Codec;
+use pallet_contracts_primitives::{ContractExecResult, ContractInstantiateResult};
+use pallet_contracts_rpc::{CallRequest, ContractsApi, InstantiateRequest};
+use jsonrpc_core::{Error, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{Bytes, H256};
+use sp_rpc::number::NumberOrHex;
+use sp_runtime::traits::Block as BlockT;
+
+use super::{at_or_best, decode_number, runtime_error_into_rpc_err, INVALID_PARAMS};
+
+/// The supplied ink! metadata could not be interpreted.
+const INVALID_METADATA: i64 = 2;
+
+/// The dry-run of a contract instantiation failed.
+const INSTANTIATION_FAILED: i64 = 4;
+
+/// The outcome of comparing an ink! metadata blob against on-chain code.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataVerification<Hash> {
+	/// `true` if the code hash of the metadata matches the on-chain code hash.
+	pub matches: bool,
+	/// The code hash found in the metadata's `source.hash` field.
+	pub metadata_code_hash: Hash,
+	/// The code hash of the contract on-chain, `None` if there is no contract at the address.
+	pub on_chain_code_hash: Option<Hash>,
+}
+
+impl<Hash: PartialEq> MetadataVerification<Hash> {
+	/// Compares the code hash of the metadata against the one of the contract on-chain.
+	pub fn new(metadata_code_hash: Hash, on_chain_code_hash: Option<Hash>) -> Self {
+		Self {
+			matches: on_chain_code_hash.as_ref() == Some(&metadata_code_hash),
+			metadata_code_hash,
+			on_chain_code_hash,
+		}
+	}
+}
+
+/// Who deployed a contract and when.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Provenance<AccountId> {
+	/// The account which instantiated the contract.
+	pub deployer: AccountId,
+	/// The number of the block the contract was instantiated in.
+	pub block: BlockNumber,
+}
+
+/// An event emitted by a contract.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContractEvent {
+	/// The number of the block the event was emitted in.
+	pub block: BlockNumber,
+	/// The SCALE encoded event as emitted by the contract.
+	pub data: Bytes,
+}
+
+/// The estimated cost of instantiating a contract.
+///
+/// Balances do not fit into a JSON number, hence they are hex strings if they exceed `u64`.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstantiateEstimate {
+	/// The gas consumed by a dry-run of the instantiation.
+	///
+	/// This is the `gas_limit` the fee is estimated for.
+	pub gas_consumed: u64,
+	/// The transaction fee, including the fee for `gas_consumed`.
+	pub fee: NumberOrHex,
+	/// The endowment transferred to the new contract.
+	pub endowment: NumberOrHex,
+	/// The minimum free balance the origin needs: `fee + endowment`.
+	pub total: NumberOrHex,
+}
+
+/// The minimum free balance needed to instantiate a contract.
+///
+/// Balances do not fit into a JSON number, hence they are hex strings if they exceed `u64`.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MinimumBalance {
+	/// The balance which has to remain in the origin to keep it alive.
+	pub existential_deposit: NumberOrHex,
+	/// The minimum endowment of the contract.
+	pub endowment: NumberOrHex,
+	/// The estimated transaction fee of the `instantiate_with_code` call.
+	pub fee: NumberOrHex,
+	/// The sum of all of the above.
+	pub total: NumberOrHex,
+}
+
+/// The deposits the contracts pallet uses to calculate the rent.
+///
+/// Balances do not fit into a JSON number, hence they are hex strings if they exceed `u64`.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DepositParams {
+	/// The deposit for every contract.
+	pub deposit_per_contract: NumberOrHex,
+	/// The deposit for every byte of contract storage.
+	pub deposit_per_storage_byte: NumberOrHex,
+	/// The deposit for every item of contract storage.
+	pub deposit_per_storage_item: NumberOrHex,
+	/// The deposit needed to leave a tombstone.
+	pub tombstone_deposit: NumberOrHex,
+}
+
+/// Canvas specific contracts RPC methods.
+#[rpc]
+pub trait CanvasContractsApi<BlockHash, AccountId, Balance, Hash> {
+	/// Checks whether the given ink! metadata belongs to the contract at `address`.
+	///
+	/// The chain does not store ABIs, so this compares the code hash recorded in the
+	/// metadata with the code hash of the contract deployed at `address`.
+	/// The metadata can be supplied either as a JSON object or as a JSON encoded string.
+	#[rpc(name = "contracts_verifyMetadata")]
+	fn verify_metadata(
+		&self,
+		address: AccountId,
+		metadata: serde_json::Value,
+		at: Option<BlockHash>,
+	) -> Result<MetadataVerification<Hash>>;
+
+	/// Returns `true` if `address` belongs to a contract rather than to a plain account.
+	#[rpc(name = "contracts_isContract")]
+	fn is_contract(&self, address: AccountId, at: Option<BlockHash>) -> Result<bool>;
+
+	/// Returns the child tries of terminated contracts which are pending deletion.
+	///
+	/// At most 256 trie ids are returned; `len` is the length of the whole queue.
+	#[rpc(name = "contracts_deletionQueue")]
+	fn deletion_queue(&self, at: Option<BlockHash>) -> Result<DeletionQueue>;
+
+	/// Estimates the total cost of instantiating a contract.
+	///
+	/// The instantiation is dry-run to determine the gas it consumes. The result contains
+	/// the transaction fee for a `gas_limit` of the consumed gas and the endowment, which
+	/// together are the minimum free balance the origin needs.
+	#[rpc(name = "contracts_estimateInstantiate")]
+	fn estimate_instantiate(
+		&self,
+		instantiate_request: InstantiateRequest<AccountId, Hash>,
+		at: Option<BlockHash>,
+	) -> Result<InstantiateEstimate>;
+
+	/// Returns the minimum free balance needed to upload and instantiate a contract.
+	///
+	/// Combines the existential deposit of the origin, the minimum endowment (including
+	/// the storage deposit while rent is enabled) and an estimated fee. This is meant as
+	/// a hint for users; the actual cost depends on the gas the instantiation consumes.
+	#[rpc(name = "contracts_minBalanceToInstantiate")]
+	fn min_balance_to_instantiate(
+		&self,
+		code_len: u32,
+		storage_bytes: u32,
+		at: Option<BlockHash>,
+	) -> Result<MinimumBalance>;
+
+	/// Returns the number of successful calls into the contract at `address`.
+	///
+	/// Instantiations, failed calls and calls from other contracts are not counted.
+	#[rpc(name = "contracts_callCount")]
+	fn call_count(&self, address: AccountId, at: Option<BlockHash>) -> Result<u64>;
+
+	/// Returns the number of alive contracts and the bytes and items they store.
+	///
+	/// This is meant for capacity planning. The totals are maintained as contracts are
+	/// instantiated and called, so the query does not iterate over the contracts.
+	#[rpc(name = "contracts_totalStorage")]
+	fn total_storage(&self, at: Option<BlockHash>) -> Result<StorageUsage>;
+
+	/// Returns the deployer of the contract at `address` and the block it was instantiated in.
+	///
+	/// Returns `null` for contracts instantiated by other contracts and for plain accounts.
+	#[rpc(name = "contracts_provenance")]
+	fn provenance(
+		&self,
+		address: AccountId,
+		at: Option<BlockHash>,
+	) -> Result<Option<Provenance<AccountId>>>;
+
+	/// Returns up to `count` (at most 256) events emitted by the contract at `address`,
+	/// newest first.
+	///
+	/// Only the events of the last `EventRetention` blocks (an hour on Canvas) are kept.
+	#[rpc(name = "contracts_recentEvents")]
+	fn recent_events(
+		&self,
+		address: AccountId,
+		count: u32,
+		at: Option<BlockHash>,
+	) -> Result<Vec<ContractEvent>>;
+
+	/// Returns the deposits the contracts pallet uses to calculate the rent.
+	#[rpc(name = "contracts_depositParams")]
+	fn deposit_params(&self, at: Option<BlockHash>) -> Result<DepositParams>;
+}
+
+/// An implementation of Canvas specific contract RPC methods.
+pub struct CanvasContracts<C, B> {
+	client: Arc<C>,
+	_marker: PhantomData<B>,
+}
+
+impl<C, B> CanvasContracts<C, B> {
+	/// Create new `CanvasContracts` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+impl<C, Block, AccountId, Balance, Hash>
+	CanvasContractsApi<<Block as BlockT>::Hash, AccountId, Balance, Hash>
+	for CanvasContracts<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static,
+	C: ProvideRuntimeApi<Block>,
+	C: HeaderBackend<Block>,
+	C::Api: CanvasContractsRuntimeApi<Block, AccountId, Balance, Hash>,
+	AccountId: Codec,
+	Balance: Codec + TryFrom<NumberOrHex> + Into<NumberOrHex>,
+	Hash: Codec + PartialEq + DeserializeOwned,
+{
+	fn verify_metadata(
+		&self,
+		address: AccountId,
+		metadata: serde_json::Value,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<MetadataVerification<Hash>> {
+		let metadata_code_hash = metadata_code_hash(metadata)?;
+
+		let api = self.client.runtime_api();
+		let at = at_or_best(&*self.client, at);
+
+		let on_chain_code_hash = api.code_hash(&at, address).map_err(runtime_error_into_rpc_err)?;
+
+		Ok(MetadataVerification::new(metadata_code_hash, on_chain_code_hash))
+	}
+
+	fn is_contract(&self, address: AccountId, at: Option<<Block as BlockT>::Hash>) -> Result<bool> {
+		let api = self.client.runtime_api();
+		let at = at_or_best(&*self.client, at);
+
+		api.is_contract(&at, address).map_err(runtime_error_into_rpc_err)
+	}
+
+	fn deletion_queue(&self, at: Option<<Block as BlockT>::Hash>) -> Result<DeletionQueue> {
+		let api = self.client.runtime_api();
+		let at = at_or_best(&*self.client, at);
+
+		api.deletion_queue(&at).map_err(runtime_error_into_rpc_err)
+	}
+
+	fn estimate_instantiate(
+		&self,
+		instantiate_request: InstantiateRequest<AccountId, Hash>,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<InstantiateEstimate> {
+		let api = self.client.runtime_api();
+		let at = at_or_best(&*self.client, at);
+
+		let InstantiateRequest {
+			origin,
+			endowment,
+			gas_limit,
+			code,
+			data,
+			salt,
+		} = instantiate_request;
+
+		let endowment: Balance = decode_number(endowment, "balance")?;
+		let gas_limit: u64 = decode_number(gas_limit, "gas_limit")?;
+
+		let estimate = api
+			.estimate_instantiate(&at, origin, endowment, gas_limit, code, data.to_vec(), salt.to_vec())
+			.map_err(runtime_error_into_rpc_err)?
+			.map_err(|e| Error {
+				code: ErrorCode::ServerError(INSTANTIATION_FAILED),
+				message: "The instantiation failed".into(),
+				data: Some(format!("{:?}", e).into()),
+			})?;
+
+		Ok(InstantiateEstimate {
+			gas_consumed: estimate.gas_consumed,
+			fee: estimate.fee.into(),
+			endowment: estimate.endowment.into(),
+			total: estimate.total.into(),
+		})
+	}
+
+	fn min_balance_to_instantiate(
+		&self,
+		code_len: u32,
+		storage_bytes: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<MinimumBalance> {
+		let api = self.client.runtime_api();
+		let at = at_or_best(&*self.client, at);
+
+		let minimum = api.min_balance_to_instantiate(&at, code_len, storage_bytes)
+			.map_err(runtime_error_into_rpc_err)?;
+
+		Ok(MinimumBalance {
+			existential_deposit: minimum.existential_deposit.into(),
+			endowment: minimum.endowment.into(),
+			fee: minimum.fee.into(),
+			total: minimum.total.into(),
+		})
+	}
+
+	fn call_count(&self, address: AccountId, at: Option<<Block as BlockT>::Hash>) -> Result<u64> {
+		let api = self.client.runtime_api();
+		let at = at_or_best(&*self.client, at);
+
+		api.call_count(&at, address).map_err(runtime_error_into_rpc_err)
+	}
+
+	fn total_storage(&self, at: Option<<Block as BlockT>::Hash>) -> Result<StorageUsage> {
+		let api = self.client.runtime_api();
+		let at = at_or_best(&*self.client, at);
+
+		api.total_storage(&at).map_err(runtime_error_into_rpc_err)
+	}
+
+	fn provenance(
+		&self,
+		address: AccountId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Option<Provenance<AccountId>>> {
+		let api = self.client.runtime_api();
+		let at = at_or_best(&*self.client, at);
+
+		let provenance = api.provenance(&at, address).map_err(runtime_error_into_rpc_err)?;
+		Ok(provenance.map(|(deployer, block)| Provenance { deployer, block }))
+	}
+
+	fn recent_events(
+		&self,
+		address: AccountId,
+		count: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<ContractEvent>> {
+		let api = self.client.runtime_api();
+		let at = at_or_best(&*self.client, at);
+
+		let events = api.recent_events(&at, address, count).map_err(runtime_error_into_rpc_err)?;
+		Ok(events
+			.into_iter()
+			.map(|(block, data)| ContractEvent { block, data: data.into() })
+			.collect())
+	}
+
+	fn deposit_params(&self, at: Option<<Block as BlockT>::Hash>) -> Result<DepositParams> {
+		let api = self.client.runtime_api();
+		let at = at_or_best(&*self.client, at);
+
+		let params = api.deposit_params(&at).map_err(runtime_error_into_rpc_err)?;
+		Ok(DepositParams {
+			deposit_per_contract: params.deposit_per_contract.into(),
+			deposit_per_storage_byte: params.deposit_per_storage_byte.into(),
+			deposit_per_storage_item: params.deposit_per_storage_item.into(),
+			tombstone_deposit: params.tombstone_deposit.into(),
+		})
+	}
+}
+
+/// Extracts the code hash from the `source.hash` field of ink! metadata.
+fn metadata_code_hash<Hash: DeserializeOwned>(metadata: serde_json::Value) -> Result<Hash> {
+	let metadata: serde_json::Value = match metadata {
+		serde_json::Value::String(json) => serde_json::from_str(&json)
+			.map_err(|e| invalid_metadata(format!("Metadata is not valid JSON: {}", e)))?,
+		metadata => metadata,
+	};
+
+	let hash = metadata
+		.get("source")
+		.and_then(|source| source.get("hash"))
+		.cloned()
+		.ok_or_else(|| invalid_metadata("Metadata does not contain a `source.hash` field".into()))?;
+
+	serde_json::from_value(hash)
+		.map_err(|e| invalid_metadata(format!("Metadata contains an invalid code hash: {}", e)))
+}
+
+fn invalid_metadata(message: String) -> Error {
+	Error {
+		code: ErrorCode::ServerError(INVALID_METADATA),
+		message,
+		data: None,
+	}
+}
+
+/// Wraps the `pallet_contracts_rpc` methods and rejects dry-runs whose `gas_limit`
+/// is below a floor before they reach the runtime.
+///
+/// Such dry-runs always run out of gas, so the caller is told early instead.
+pub struct MinGasLimit<I> {
+	inner: I,
+	min_gas_limit: u64,
+}
+
+impl<I> MinGasLimit<I> {
+	/// Create new `MinGasLimit` which forwards to `inner`.
+	pub fn new(inner: I, min_gas_limit: u64) -> Self {
+		Self { inner, min_gas_limit }
+	}
+
+	fn ensure_gas_limit(&self, gas_limit: NumberOrHex) -> Result<()> {
+		match u64::try_from(gas_limit) {
+			Ok(gas_limit) if gas_limit < self.min_gas_limit => Err(Error {
+				code: ErrorCode::ServerError(INVALID_PARAMS),
+				message: format!(
+					"gas_limit {} is below the minimum of {}, the contract would run out of gas",
+					gas_limit,
+					self.min_gas_limit,
+				),
+				data: None,
+			}),
+			// Limits which do not fit are rejected by the wrapped implementation.
+			_ => Ok(()),
+		}
+	}
+}
+
+impl<I, BlockHash, BlockNumber, AccountId, Balance, Hash>
+	ContractsApi<BlockHash, BlockNumber, AccountId, Balance, Hash> for MinGasLimit<I>
+where
+	I: ContractsApi<BlockHash, BlockNumber, AccountId, Balance, Hash>,
+{
+	fn call(
+		&self,
+		call_request: CallRequest<AccountId>,
+		at: Option<BlockHash>,
+	) -> Result<ContractExecResult> {
+		self.ensure_gas_limit(call_request.gas_limit)?;
+		self.inner.call(call_request, at)
+	}
+
+	fn instantiate(
+		&self,
+		instantiate_request: InstantiateRequest<AccountId, Hash>,
+		at: Option<BlockHash>,
+	) -> Result<ContractInstantiateResult<AccountId, BlockNumber>> {
+		self.ensure_gas_limit(instantiate_request.gas_limit)?;
+		self.inner.instantiate(instantiate_request, at)
+	}
+
+	fn get_storage(
+		&self,
+		address: AccountId,
+		key: H256,
+		at: Option<BlockHash>,
+	) -> Result<Option<Bytes>> {
+		self.inner.get_storage(address, key, at)
+	}
+
+	fn rent_projection(
+		&self,
+		address: AccountId,
+		at: Option<BlockHash>,
+	) -> Result<Option<BlockNumber>> {
+		self.inner.rent_projection(address, at)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const CODE_HASH: &str = "0x9a6bd8c1d2e7b6e5d2f0e7fdbb7ba3a6a3a1c5f0c1f2b49d6c8dbd6d3a9f5e11";
+
+	/// The parts of the metadata generated by `cargo contract` which are relevant here.
+	fn metadata(code_hash: &str) -> serde_json::Value {
+		serde_json::json!({
+			"metadataVersion": "0.1.0",
+			"source": {
+				"hash": code_hash,
+				"language": "ink! 3.0.0-rc3",
+				"compiler": "rustc 1.53.0-nightly"
+			},
+			"contract": {
+				"name": "flipper",
+				"version": "0.1.0",
+				"authors": ["Parity Technologies <admin@parity.io>"]
+			},
+			"spec": {},
+			"storage": {},
+			"types": []
+		})
+	}
+
+	fn hash(hex: &str) -> H256 {
+		serde_json::from_value(serde_json::Value::String(hex.into())).unwrap()
+	}
+
+	#[test]
+	fn matching_metadata_is_verified() {
+		let metadata_code_hash: H256 = metadata_code_hash(metadata(CODE_HASH)).unwrap();
+		let verification = MetadataVerification::new(metadata_code_hash, Some(hash(CODE_HASH)));
+
+		assert!(verification.matches);
+		assert_eq!(verification.metadata_code_hash, hash(CODE_HASH));
+	}
+
+	#[test]
+	fn metadata_of_another_contract_does_not_match() {
+		let other = "0x1111111111111111111111111111111111111111111111111111111111111111";
+		let metadata_code_hash: H256 = metadata_code_hash(metadata(other)).unwrap();
+		let verification = MetadataVerification::new(metadata_code_hash, Some(hash(CODE_HASH)));
+
+		assert!(!verification.matches);
+		assert_eq!(verification.metadata_code_hash, hash(other));
+		assert_eq!(verification.on_chain_code_hash, Some(hash(CODE_HASH)));
+	}
+
+	#[test]
+	fn metadata_does_not_match_a_plain_account() {
+		let metadata_code_hash: H256 = metadata_code_hash(metadata(CODE_HASH)).unwrap();
+		assert!(!MetadataVerification::new(metadata_code_hash, None).matches);
+	}
+
+	#[test]
+	fn metadata_can_be_passed_as_string() {
+		let metadata = serde_json::Value::String(metadata(CODE_HASH).to_string());
+		assert_eq!(metadata_code_hash::<H256>(metadata).unwrap(), hash(CODE_HASH));
+	}
+
+	#[test]
+	fn metadata_without_code_hash_is_rejected() {
+		let mut metadata = metadata(CODE_HASH);
+		metadata["source"].as_object_mut().unwrap().remove("hash");
+		let error = metadata_code_hash::<H256>(metadata).unwrap_err();
+		assert_eq!(error.code, ErrorCode::ServerError(INVALID_METADATA));
+	}
+
+	/// The error `Forwarding` answers every request with, to tell that it was forwarded.
+	const FORWARDED: i64 = 100;
+
+	/// A contracts RPC which only tells that requests reached it.
+	struct Forwarding;
+
+	fn forwarded<T>() -> Result<T> {
+		Err(Error { code: ErrorCode::ServerError(FORWARDED), message: "forwarded".into(), data: None })
+	}
+
+	impl ContractsApi<H256, BlockNumber, H256, u128, H256> for Forwarding {
+		fn call(&self, _: CallRequest<H256>, _: Option<H256>) -> Result<ContractExecResult> {
+			forwarded()
+		}
+
+		fn instantiate(
+			&self,
+			_: InstantiateRequest<H256, H256>,
+			_: Option<H256>,
+		) -> Result<ContractInstantiateResult<H256, BlockNumber>> {
+			forwarded()
+		}
+
+		fn get_storage(&self, _: H256, _: H256, _: Option<H256>) -> Result<Option<Bytes>> {
+			forwarded()
+		}
+
+		fn rent_projection(&self, _: H256, _: Option<H256>) -> Result<Option<BlockNumber>> {
+			forwarded()
+		}
+	}
+
+	fn call_request(gas_limit: u64) -> CallRequest<H256> {
+		CallRequest {
+			origin: H256::zero(),
+			dest: H256::zero(),
+			value: 0u64.into(),
+			gas_limit: gas_limit.into(),
+			input_data: Bytes(vec![]),
+		}
+	}
+
+	fn instantiate_request(gas_limit: u64) -> InstantiateRequest<H256, H256> {
+		InstantiateRequest {
+			origin: H256::zero(),
+			endowment: 0u64.into(),
+			gas_limit: gas_limit.into(),
+			code: pallet_contracts_primitives::Code::Existing(H256::zero()),
+			data: Bytes(vec![]),
+			salt: Bytes(vec![]),
+		}
+	}
+
+	#[test]
+	fn gas_limits_below_the_minimum_are_rejected_before_the_runtime() {
+		let api = MinGasLimit::new(Forwarding, 1_000);
+
+		let error = api.call(call_request(999), None).unwrap_err();
+		assert_eq!(error.code, ErrorCode::ServerError(INVALID_PARAMS));
+		assert!(error.message.contains("below the minimum of 1000"));
+
+		let error = api.instantiate(instantiate_request(0), None).unwrap_err();
+		assert_eq!(error.code, ErrorCode::ServerError(INVALID_PARAMS));
+	}
+
+	#[test]
+	fn sufficient_gas_limits_are_passed_through() {
+		let api = MinGasLimit::new(Forwarding, 1_000);
+
+		let error = api.call(call_request(1_000), None).unwrap_err();
+		assert_eq!(error.code, ErrorCode::ServerError(FORWARDED));
+		let error = api.instantiate(instantiate_request(u64::MAX), None).unwrap_err();
+		assert_eq!(error.code, ErrorCode::ServerError(FORWARDED));
+		let error = api.get_storage(H256::zero(), H256::zero(), None).unwrap_err();
+		assert_eq!(error.code, ErrorCode::ServerError(FORWARDED));
+	}
+
+	#[test]
+	fn metadata_is_verified_against_an_instantiated_contract() {
+		use crate::test_chain;
+		use canvas_runtime::{opaque::Block, AccountId, Balance, DOLLARS};
+		use sp_keyring::AccountKeyring;
+
+		let client = test_chain::new_client();
+		let (address, code_hash) = test_chain::instantiate(
+			&client,
+			AccountKeyring::Alice,
+			test_chain::DUMMY_CONTRACT,
+			100 * DOLLARS,
+		);
+		let contracts = CanvasContracts::<_, Block>::new(client);
+		let verify = |address: AccountId, code_hash: H256| {
+			let code_hash = serde_json::to_value(code_hash).unwrap();
+			CanvasContractsApi::<H256, AccountId, Balance, H256>::verify_metadata(
+				&contracts,
+				address,
+				metadata(code_hash.as_str().unwrap()),
+				None,
+			)
+			.unwrap()
+		};
+
+		let verification = verify(address.clone(), code_hash);
+		assert!(verification.matches);
+		assert_eq!(verification.on_chain_code_hash, Some(code_hash));
+
+		let verification = verify(address, H256::repeat_byte(1));
+		assert!(!verification.matches);
+		assert_eq!(verification.metadata_code_hash, H256::repeat_byte(1));
+		assert_eq!(verification.on_chain_code_hash, Some(code_hash));
+	}
+}
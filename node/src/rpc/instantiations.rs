@@ -0,0 +1,244 @@
+//! Subscription to the contract instantiations in imported blocks.
+
+use std::sync::Arc;
+
+use canvas_runtime::{
+	runtime_api::CanvasContractsApi as CanvasContractsRuntimeApi, AccountId, Balance,
+	ContractsEvent, Event, Hash,
+};
+use canvas_runtime::opaque::Block;
+use futures::{FutureExt, SinkExt, Stream, StreamExt};
+use jsonrpc_core::Result;
+use jsonrpc_derive::rpc;
+use jsonrpc_pubsub::{manager::SubscriptionManager, typed::Subscriber, SubscriptionId};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use sc_client_api::{BlockchainEvents, StorageProvider};
+use sp_api::ProvideRuntimeApi;
+use sp_runtime::generic::BlockId;
+
+/// A contract instantiation contained in an imported block.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Instantiation {
+	/// The hash of the block which contains the instantiation.
+	pub block_hash: Hash,
+	/// The account which instantiated the contract.
+	pub deployer: AccountId,
+	/// The address of the new contract.
+	pub contract: AccountId,
+	/// The code hash of the new contract, `None` if it was already terminated
+	/// within the same block.
+	pub code_hash: Option<Hash>,
+}
+
+/// Subscription to contract instantiations.
+#[rpc]
+pub trait ContractsInstantiationsApi {
+	/// RPC Metadata
+	type Metadata;
+
+	/// Notifies about every contract instantiated in an imported block.
+	///
+	/// Blocks are scanned as they are imported, so instantiations in blocks which
+	/// do not end up in the canonical chain are notified as well.
+	#[pubsub(
+		subscription = "contracts_instantiation",
+		subscribe,
+		name = "contracts_subscribeInstantiations"
+	)]
+	fn subscribe_instantiations(&self, metadata: Self::Metadata, subscriber: Subscriber<Instantiation>);
+
+	/// Unsubscribe from the contract instantiations.
+	#[pubsub(
+		subscription = "contracts_instantiation",
+		unsubscribe,
+		name = "contracts_unsubscribeInstantiations"
+	)]
+	fn unsubscribe_instantiations(
+		&self,
+		metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> Result<bool>;
+}
+
+/// An implementation of the contract instantiations subscription.
+pub struct ContractsInstantiations<C, BE> {
+	client: Arc<C>,
+	subscriptions: SubscriptionManager,
+	_marker: std::marker::PhantomData<BE>,
+}
+
+impl<C, BE> ContractsInstantiations<C, BE> {
+	/// Create new `ContractsInstantiations` with the given reference to the client.
+	pub fn new(client: Arc<C>, subscriptions: SubscriptionManager) -> Self {
+		Self { client, subscriptions, _marker: Default::default() }
+	}
+}
+
+/// Returns the contract instantiations recorded in the events of block `hash`.
+fn instantiations_in_block<C, BE>(client: &C, hash: Hash) -> Vec<Instantiation> where
+	BE: sc_client_api::Backend<Block>,
+	C: ProvideRuntimeApi<Block> + StorageProvider<Block, BE>,
+	C::Api: CanvasContractsRuntimeApi<Block, AccountId, Balance, Hash>,
+{
+	let at = BlockId::Hash(hash);
+	let events = crate::contract_activity::events_of_block(client, hash);
+
+	let api = client.runtime_api();
+	instantiations_in_events(hash, events, |contract| api.code_hash(&at, contract).ok().flatten())
+}
+
+/// Returns the contract instantiations recorded in `events` of block `hash`.
+fn instantiations_in_events(
+	hash: Hash,
+	events: Vec<frame_system::EventRecord<Event, Hash>>,
+	code_hash: impl Fn(AccountId) -> Option<Hash>,
+) -> Vec<Instantiation> {
+	events.into_iter()
+		.filter_map(|record| match record.event {
+			Event::Contracts(ContractsEvent::Instantiated(deployer, contract)) => {
+				let code_hash = code_hash(contract.clone());
+				Some(Instantiation { block_hash: hash, deployer, contract, code_hash })
+			},
+			_ => None,
+		})
+		.collect()
+}
+
+/// Turns the hashes of `imported` blocks into the instantiations which `scan` finds in them.
+fn instantiations_of_blocks(
+	imported: impl Stream<Item = Hash>,
+	scan: impl Fn(Hash) -> Vec<Instantiation>,
+) -> impl Stream<Item = Instantiation> {
+	imported.map(move |hash| futures::stream::iter(scan(hash))).flatten()
+}
+
+impl<C, BE> ContractsInstantiationsApi for ContractsInstantiations<C, BE> where
+	BE: sc_client_api::Backend<Block> + 'static,
+	C: ProvideRuntimeApi<Block> + StorageProvider<Block, BE> + BlockchainEvents<Block>,
+	C: Send + Sync + 'static,
+	C::Api: CanvasContractsRuntimeApi<Block, AccountId, Balance, Hash>,
+{
+	type Metadata = sc_rpc::Metadata;
+
+	fn subscribe_instantiations(&self, _metadata: Self::Metadata, subscriber: Subscriber<Instantiation>) {
+		let client = self.client.clone();
+		let stream = instantiations_of_blocks(
+			self.client.import_notification_stream().map(|notification| notification.hash),
+			move |hash| instantiations_in_block(&*client, hash),
+		)
+			.map(|instantiation| Ok::<_, ()>(Ok(instantiation)));
+
+		self.subscriptions.add(subscriber, |sink| {
+			stream
+				.forward(sink.sink_map_err(|e| warn!("Error sending notifications: {:?}", e)))
+				// The stream only ends when the subscriber is gone.
+				.map(|_| ())
+		});
+	}
+
+	fn unsubscribe_instantiations(
+		&self,
+		_metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> Result<bool> {
+		Ok(self.subscriptions.cancel(id))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_system::{EventRecord, Phase};
+
+	fn record(event: Event) -> EventRecord<Event, Hash> {
+		EventRecord { phase: Phase::ApplyExtrinsic(1), event, topics: vec![] }
+	}
+
+	fn instantiation(block: u8, deployer: u8, contract: u8) -> Instantiation {
+		Instantiation {
+			block_hash: Hash::repeat_byte(block),
+			deployer: AccountId::from([deployer; 32]),
+			contract: AccountId::from([contract; 32]),
+			code_hash: Some(Hash::repeat_byte(contract)),
+		}
+	}
+
+	#[test]
+	fn only_instantiated_events_are_instantiations() {
+		let events = vec![
+			record(Event::System(frame_system::Event::NewAccount([2; 32].into()))),
+			record(Event::Contracts(ContractsEvent::Instantiated([1; 32].into(), [3; 32].into()))),
+			record(Event::Contracts(ContractsEvent::Terminated([4; 32].into(), [1; 32].into()))),
+		];
+		let code_hash = |contract: AccountId| Some(Hash::from(<[u8; 32]>::from(contract)));
+
+		assert_eq!(
+			instantiations_in_events(Hash::repeat_byte(7), events, code_hash),
+			vec![instantiation(7, 1, 3)],
+		);
+	}
+
+	#[test]
+	fn every_instantiation_of_an_imported_block_is_notified_once() {
+		let scan = |hash: Hash| match hash.as_bytes()[0] {
+			1 => vec![instantiation(1, 1, 3)],
+			3 => vec![instantiation(3, 2, 4), instantiation(3, 2, 5)],
+			_ => vec![],
+		};
+		let imported = futures::stream::iter((1..=3).map(Hash::repeat_byte));
+
+		let notified: Vec<_> = futures::executor::block_on(
+			instantiations_of_blocks(imported, scan).collect(),
+		);
+		assert_eq!(
+			notified,
+			vec![instantiation(1, 1, 3), instantiation(3, 2, 4), instantiation(3, 2, 5)],
+		);
+	}
+
+	#[test]
+	fn instantiations_are_notified_until_unsubscribed() {
+		use crate::test_chain;
+		use canvas_runtime::DOLLARS;
+		use sp_blockchain::HeaderBackend;
+		use sp_keyring::AccountKeyring;
+
+		let client = test_chain::new_client();
+		let executor = sc_rpc::SubscriptionTaskExecutor::new(sp_core::testing::TaskExecutor::new());
+		let api = ContractsInstantiations::<_, test_chain::Backend>::new(
+			client.clone(),
+			SubscriptionManager::new(Arc::new(executor)),
+		);
+
+		let (subscriber, id, mut transport) = Subscriber::new_test("test");
+		api.subscribe_instantiations(Default::default(), subscriber);
+		let id = futures::executor::block_on(id).unwrap().unwrap();
+
+		let (contract, code_hash) = test_chain::instantiate(
+			&client,
+			AccountKeyring::Alice,
+			test_chain::DUMMY_CONTRACT,
+			100 * DOLLARS,
+		);
+		let notification = futures::executor::block_on(transport.next()).unwrap();
+		let notification: serde_json::Value = serde_json::from_str(&notification).unwrap();
+		let notified: Instantiation =
+			serde_json::from_value(notification["params"]["result"].clone()).unwrap();
+		assert_eq!(
+			notified,
+			Instantiation {
+				block_hash: client.info().best_hash,
+				deployer: AccountKeyring::Alice.to_account_id(),
+				contract,
+				code_hash: Some(code_hash),
+			},
+		);
+
+		assert_eq!(api.unsubscribe_instantiations(None, id.clone()), Ok(true));
+		assert_eq!(api.unsubscribe_instantiations(None, id), Ok(false));
+		// The subscription is dropped together with its end of the transport.
+		assert_eq!(futures::executor::block_on(transport.next()), None);
+	}
+}
@@ -0,0 +1,151 @@
+//! Canvas specific metrics RPC methods.
+//!
+//! Exposes the metrics which the node registers for Prometheus (e.g. the block height,
+//! the peer count and the contract activity) to environments which do not run a
+//! Prometheus scraper.
+
+use std::collections::BTreeMap;
+
+use jsonrpc_core::{Error, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use substrate_prometheus_endpoint::{prometheus::proto::MetricType, Registry};
+
+use super::DenyUnsafe;
+
+/// The Prometheus endpoint of the node is disabled.
+const METRICS_DISABLED: i64 = 5;
+
+/// Canvas specific metrics RPC methods.
+#[rpc]
+pub trait CanvasMetricsApi {
+	/// Returns the current value of every counter and gauge the node registered.
+	///
+	/// The keys are the metric names followed by their labels in the Prometheus text
+	/// format, e.g. `substrate_block_height{status="best"}`. Histograms are omitted.
+	///
+	/// This is an unsafe method: Prometheus only listens on localhost by default, so the
+	/// internals of the node are not exposed on public RPC ports either.
+	#[rpc(name = "canvas_metrics")]
+	fn metrics(&self) -> Result<BTreeMap<String, f64>>;
+}
+
+/// An implementation of Canvas specific metrics RPC methods.
+pub struct CanvasMetrics {
+	registry: Option<Registry>,
+	deny_unsafe: DenyUnsafe,
+}
+
+impl CanvasMetrics {
+	/// Create new `CanvasMetrics` reading from `registry`, `None` if Prometheus is disabled.
+	pub fn new(registry: Option<Registry>, deny_unsafe: DenyUnsafe) -> Self {
+		Self { registry, deny_unsafe }
+	}
+}
+
+impl CanvasMetricsApi for CanvasMetrics {
+	fn metrics(&self) -> Result<BTreeMap<String, f64>> {
+		self.deny_unsafe.check_if_safe()?;
+		let registry = self.registry.as_ref().ok_or_else(|| Error {
+			code: ErrorCode::ServerError(METRICS_DISABLED),
+			message: "Metrics are not collected, the node runs with `--no-prometheus`".into(),
+			data: None,
+		})?;
+
+		let mut snapshot = BTreeMap::new();
+		for family in registry.gather() {
+			for metric in family.get_metric() {
+				let value = match family.get_field_type() {
+					MetricType::COUNTER => metric.get_counter().get_value(),
+					MetricType::GAUGE => metric.get_gauge().get_value(),
+					_ => continue,
+				};
+				let labels = metric.get_label()
+					.iter()
+					.map(|label| format!("{}=\"{}\"", label.get_name(), label.get_value()))
+					.collect::<Vec<_>>();
+				let key = if labels.is_empty() {
+					family.get_name().to_string()
+				} else {
+					format!("{}{{{}}}", family.get_name(), labels.join(","))
+				};
+				snapshot.insert(key, value);
+			}
+		}
+		Ok(snapshot)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use substrate_prometheus_endpoint::{
+		register, Counter, GaugeVec, Histogram, HistogramOpts, Opts, U64,
+	};
+
+	#[test]
+	fn counters_and_gauges_are_keyed_by_name_and_labels() {
+		let registry = Registry::new();
+		let height = register(
+			GaugeVec::<U64>::new(Opts::new("substrate_block_height", "Block height"), &["status"])
+				.unwrap(),
+			&registry,
+		)
+		.unwrap();
+		height.with_label_values(&["best"]).set(3);
+		height.with_label_values(&["finalized"]).set(2);
+		let imported = register(
+			Counter::<U64>::new("substrate_imported_blocks", "Imported blocks").unwrap(),
+			&registry,
+		)
+		.unwrap();
+		imported.inc_by(3);
+		register(
+			Histogram::with_opts(HistogramOpts::new("substrate_block_verification_time", "Time"))
+				.unwrap(),
+			&registry,
+		)
+		.unwrap();
+
+		let metrics = CanvasMetrics::new(Some(registry), DenyUnsafe::No).metrics().unwrap();
+		let keys = metrics.keys().map(String::as_str).collect::<Vec<_>>();
+		assert_eq!(
+			keys,
+			vec![
+				r#"substrate_block_height{status="best"}"#,
+				r#"substrate_block_height{status="finalized"}"#,
+				"substrate_imported_blocks",
+			],
+		);
+		assert_eq!(metrics[r#"substrate_block_height{status="best"}"#], 3.0);
+		assert_eq!(metrics["substrate_imported_blocks"], 3.0);
+	}
+
+	#[test]
+	fn metrics_are_unavailable_without_prometheus() {
+		let error = CanvasMetrics::new(None, DenyUnsafe::No).metrics().unwrap_err();
+		assert_eq!(error.code, ErrorCode::ServerError(METRICS_DISABLED));
+	}
+
+	#[test]
+	fn contract_activity_is_exported() {
+		let registry = Registry::new();
+		crate::contract_activity::ContractActivityMetrics::register(&registry).unwrap();
+
+		let metrics = CanvasMetrics::new(Some(registry), DenyUnsafe::No).metrics().unwrap();
+		let keys = metrics.keys().map(String::as_str).collect::<Vec<_>>();
+		assert_eq!(
+			keys,
+			vec![
+				"canvas_contract_calls",
+				"canvas_contract_gas_used",
+				"canvas_contract_instantiations",
+			],
+		);
+	}
+
+	#[test]
+	fn metrics_are_unsafe() {
+		let error = CanvasMetrics::new(Some(Registry::new()), DenyUnsafe::Yes).metrics().unwrap_err();
+		assert_eq!(error.code, ErrorCode::MethodNotFound);
+	}
+}
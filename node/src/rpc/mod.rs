@@ -0,0 +1,211 @@
+//! A collection of node-specific RPC methods.
+//! Substrate provides the `sc-rpc` crate, which defines the core RPC layer
+//! used by Substrate nodes. This file extends those RPC definitions with
+//! capabilities that are specific to this project's runtime configuration.
+
+#![warn(missing_docs)]
+
+use std::sync::Arc;
+
+use canvas_runtime::{opaque::Block, AccountId, Balance, BlockNumber, Hash, Index};
+use pallet_contracts_rpc::{Contracts, ContractsApi};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::{Error as BlockChainError, HeaderMetadata, HeaderBackend};
+use sp_block_builder::BlockBuilder;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+pub use sc_rpc_api::DenyUnsafe;
+use sp_transaction_pool::TransactionPool;
+
+pub mod balances;
+pub mod contracts;
+pub mod instantiations;
+pub mod metrics;
+pub mod system;
+
+/// A runtime call failed.
+const RUNTIME_ERROR: i64 = 1;
+
+/// A parameter could not be converted to the type expected by the runtime.
+const INVALID_PARAMS: i64 = 3;
+
+/// Converts a runtime trap into an RPC error.
+fn runtime_error_into_rpc_err(err: impl std::fmt::Debug) -> jsonrpc_core::Error {
+	jsonrpc_core::Error {
+		code: jsonrpc_core::ErrorCode::ServerError(RUNTIME_ERROR),
+		message: "Runtime error".into(),
+		data: Some(format!("{:?}", err).into()),
+	}
+}
+
+/// Converts a number supplied by the caller into the type expected by the runtime.
+fn decode_number<T: std::convert::TryFrom<sp_rpc::number::NumberOrHex>>(
+	from: sp_rpc::number::NumberOrHex,
+	name: &str,
+) -> jsonrpc_core::Result<T> {
+	T::try_from(from).map_err(|_| jsonrpc_core::Error {
+		code: jsonrpc_core::ErrorCode::ServerError(INVALID_PARAMS),
+		message: format!("{:?} does not fit into the {} type", from, name),
+		data: None,
+	})
+}
+
+/// Returns the block `at`, or the best block of `client` if the caller did not supply one.
+fn at_or_best<B: BlockT, C: HeaderBackend<B>>(client: &C, at: Option<B::Hash>) -> BlockId<B> {
+	BlockId::hash(at.unwrap_or_else(|| client.info().best_hash))
+}
+
+/// Full client dependencies.
+pub struct FullDeps<C, P> {
+	/// The client instance to use.
+	pub client: Arc<C>,
+	/// Transaction pool instance.
+	pub pool: Arc<P>,
+	/// Whether to deny unsafe calls
+	pub deny_unsafe: DenyUnsafe,
+	/// Executor for the subscriptions.
+	pub subscription_executor: sc_rpc::SubscriptionTaskExecutor,
+	/// The lowest `gas_limit` accepted by contract dry-runs.
+	pub min_gas_limit: u64,
+	/// The registry of the Prometheus metrics, `None` if they are disabled.
+	pub prometheus_registry: Option<substrate_prometheus_endpoint::Registry>,
+}
+
+/// Instantiate all full RPC extensions.
+pub fn create_full<C, P, BE>(
+	deps: FullDeps<C, P>,
+) -> jsonrpc_core::IoHandler<sc_rpc::Metadata> where
+	BE: sc_client_api::Backend<Block> + 'static,
+	C: ProvideRuntimeApi<Block>,
+	C: sc_client_api::BlockchainEvents<Block> + sc_client_api::StorageProvider<Block, BE>,
+	C: HeaderBackend<Block> + HeaderMetadata<Block, Error=BlockChainError> + 'static,
+	C: Send + Sync + 'static,
+	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
+	C::Api: pallet_contracts_rpc::ContractsRuntimeApi<Block, AccountId, Balance, BlockNumber, Hash>,
+	C::Api: canvas_runtime::runtime_api::CanvasContractsApi<Block, AccountId, Balance, Hash>,
+	C::Api: canvas_runtime::runtime_api::CanvasBalancesApi<Block, AccountId, Balance>,
+	C::Api: canvas_runtime::runtime_api::CanvasSystemApi<Block>,
+	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
+	C::Api: BlockBuilder<Block>,
+	P: TransactionPool + 'static,
+{
+	use substrate_frame_rpc_system::{FullSystem, SystemApi};
+	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
+	use contracts::{CanvasContracts, CanvasContractsApi, MinGasLimit};
+	use balances::{CanvasBalances, CanvasBalancesApi};
+	use system::{CanvasSystem, CanvasSystemApi};
+	use instantiations::{ContractsInstantiations, ContractsInstantiationsApi};
+	use metrics::{CanvasMetrics, CanvasMetricsApi};
+
+	let mut io = jsonrpc_core::IoHandler::default();
+	let FullDeps {
+		client,
+		pool,
+		deny_unsafe,
+		subscription_executor,
+		min_gas_limit,
+		prometheus_registry,
+	} = deps;
+
+	io.extend_with(
+		SystemApi::to_delegate(FullSystem::new(client.clone(), pool, deny_unsafe))
+	);
+
+	io.extend_with(
+		TransactionPaymentApi::to_delegate(TransactionPayment::new(client.clone()))
+	);
+
+	// Contracts RPC API extension
+	io.extend_with(
+		ContractsApi::to_delegate(MinGasLimit::new(Contracts::new(client.clone()), min_gas_limit))
+	);
+
+	io.extend_with(
+		CanvasContractsApi::to_delegate(CanvasContracts::new(client.clone()))
+	);
+
+	io.extend_with(
+		CanvasBalancesApi::to_delegate(CanvasBalances::new(client.clone(), deny_unsafe))
+	);
+
+	io.extend_with(
+		CanvasSystemApi::to_delegate(CanvasSystem::new(client.clone()))
+	);
+
+	io.extend_with(
+		ContractsInstantiationsApi::to_delegate(ContractsInstantiations::new(
+			client.clone(),
+			jsonrpc_pubsub::manager::SubscriptionManager::new(Arc::new(subscription_executor)),
+		))
+	);
+
+	io.extend_with(
+		CanvasMetricsApi::to_delegate(CanvasMetrics::new(prometheus_registry, deny_unsafe))
+	);
+
+	io
+}
+
+/// Dependencies of the read-only contracts query RPC.
+pub struct ContractsQueryDeps<C> {
+	/// The client instance to use.
+	pub client: Arc<C>,
+	/// Executor for the state subscriptions.
+	pub subscription_executor: sc_rpc::SubscriptionTaskExecutor,
+	/// Maximum payload of an RPC request in megabytes.
+	pub rpc_max_payload: Option<usize>,
+	/// The lowest `gas_limit` accepted by contract dry-runs.
+	pub min_gas_limit: u64,
+}
+
+/// Instantiate the RPC extensions which are exposed on the contracts query port.
+///
+/// Only read-only methods are exposed and unsafe calls are always denied:
+///
+/// * All `contracts_*` methods, which are either dry-runs or plain reads. Subscriptions
+///   are not available over HTTP.
+/// * The `state_*` and `childstate_*` methods.
+pub fn create_contracts_query<C, BE>(
+	deps: ContractsQueryDeps<C>,
+) -> jsonrpc_core::IoHandler<sc_rpc::Metadata> where
+	BE: sc_client_api::Backend<Block> + 'static,
+	C: ProvideRuntimeApi<Block>,
+	C: HeaderBackend<Block> + HeaderMetadata<Block, Error=BlockChainError> + 'static,
+	C: sc_client_api::BlockBackend<Block> + sc_client_api::BlockchainEvents<Block>,
+	C: sc_client_api::ExecutorProvider<Block> + sc_client_api::StorageProvider<Block, BE>,
+	C: sc_client_api::ProofProvider<Block> + sp_api::CallApiAt<Block>,
+	C: Send + Sync + 'static,
+	C::Api: sp_api::Metadata<Block>,
+	C::Api: pallet_contracts_rpc::ContractsRuntimeApi<Block, AccountId, Balance, BlockNumber, Hash>,
+	C::Api: canvas_runtime::runtime_api::CanvasContractsApi<Block, AccountId, Balance, Hash>,
+{
+	use sc_rpc::state::{ChildStateApi, StateApi};
+	use contracts::{CanvasContracts, CanvasContractsApi, MinGasLimit};
+
+	let mut io = jsonrpc_core::IoHandler::default();
+	let ContractsQueryDeps {
+		client,
+		subscription_executor,
+		rpc_max_payload,
+		min_gas_limit,
+	} = deps;
+
+	let (state, child_state) = sc_rpc::state::new_full(
+		client.clone(),
+		sc_rpc::SubscriptionManager::new(Arc::new(subscription_executor)),
+		DenyUnsafe::Yes,
+		rpc_max_payload,
+	);
+
+	io.extend_with(StateApi::to_delegate(state));
+	io.extend_with(ChildStateApi::to_delegate(child_state));
+
+	io.extend_with(
+		ContractsApi::to_delegate(MinGasLimit::new(Contracts::new(client.clone()), min_gas_limit))
+	);
+
+	io.extend_with(
+		CanvasContractsApi::to_delegate(CanvasContracts::new(client))
+	);
+
+	io
+}
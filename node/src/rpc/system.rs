@@ -0,0 +1,221 @@
+//! Canvas specific system RPC methods.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use canvas_runtime::{runtime_api::CanvasSystemApi as CanvasSystemRuntimeApi, Weight};
+use jsonrpc_core::Result;
+use jsonrpc_derive::rpc;
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::traits::{Block as BlockT, NumberFor};
+
+use super::{at_or_best, runtime_error_into_rpc_err};
+
+/// The top-level call of a decoded extrinsic.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedCall {
+	/// The name of the pallet, e.g. `Balances`.
+	pub pallet: String,
+	/// The name of the call, e.g. `transfer`.
+	pub call: String,
+	/// Whether the extrinsic is signed.
+	pub signed: bool,
+	/// The length of the encoded call arguments.
+	pub args_len: u32,
+}
+
+/// The storage version of a pallet.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PalletStorageVersion {
+	/// The name of the pallet in `construct_runtime!`.
+	pub pallet: String,
+	/// The version as `major.minor.patch`, `None` if no version is stored.
+	pub storage_version: Option<String>,
+}
+
+/// The best and the finalized block of the node.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Heights<Number, Hash> {
+	/// The number of the best block.
+	pub best: Number,
+	/// The hash of the best block.
+	pub best_hash: Hash,
+	/// The number of the last finalized block.
+	pub finalized: Number,
+	/// The hash of the last finalized block.
+	pub finalized_hash: Hash,
+}
+
+/// Canvas specific system RPC methods.
+#[rpc]
+pub trait CanvasSystemApi<BlockHash, BlockNumber> {
+	/// Returns the weight which is still available to `Normal` dispatches in the block.
+	///
+	/// This helps clients to size batches so that they still fit into a block.
+	#[rpc(name = "system_remainingWeight")]
+	fn remaining_weight(&self, at: Option<BlockHash>) -> Result<Weight>;
+
+	/// Returns the pallet and call name of an encoded extrinsic.
+	///
+	/// Returns `null` if the extrinsic can not be decoded by the runtime at `at`.
+	#[rpc(name = "system_decodeCall")]
+	fn decode_call(&self, extrinsic: Bytes, at: Option<BlockHash>) -> Result<Option<DecodedCall>>;
+
+	/// Returns the storage version of every pallet of the runtime.
+	///
+	/// This allows to verify that the migrations of a runtime upgrade ran.
+	#[rpc(name = "system_palletVersions")]
+	fn pallet_versions(&self, at: Option<BlockHash>) -> Result<Vec<PalletStorageVersion>>;
+
+	/// Returns whether the call filter of the runtime currently permits the encoded `call`.
+	///
+	/// Returns `null` if the call can not be decoded by the runtime at `at`. Clients can
+	/// use this to avoid submitting transactions which would be filtered anyway.
+	#[rpc(name = "system_callAllowed")]
+	fn call_allowed(&self, call: Bytes, at: Option<BlockHash>) -> Result<Option<bool>>;
+
+	/// Returns the weight of a `Utility::batch` of the SCALE encoded `Vec<Call>`.
+	///
+	/// This includes the overhead of the batch itself. Returns `null` if the calls can
+	/// not be decoded by the runtime at `at`.
+	#[rpc(name = "utility_batchWeight")]
+	fn batch_weight(&self, calls: Bytes, at: Option<BlockHash>) -> Result<Option<Weight>>;
+
+	/// Returns the best and the finalized block of the node in a single call.
+	///
+	/// Both are read at the same time, so `finalized` is never above `best`.
+	#[rpc(name = "canvas_heights")]
+	fn heights(&self) -> Result<Heights<BlockNumber, BlockHash>>;
+
+	/// Returns the expected timestamp of the block following `at` in milliseconds.
+	///
+	/// Clients can use this to choose the era of mortal transactions.
+	#[rpc(name = "timestamp_nextExpected")]
+	fn next_expected_timestamp(&self, at: Option<BlockHash>) -> Result<u64>;
+}
+
+/// An implementation of Canvas specific system RPC methods.
+pub struct CanvasSystem<C, B> {
+	client: Arc<C>,
+	_marker: PhantomData<B>,
+}
+
+impl<C, B> CanvasSystem<C, B> {
+	/// Create new `CanvasSystem` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+impl<C, Block> CanvasSystemApi<<Block as BlockT>::Hash, NumberFor<Block>>
+	for CanvasSystem<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static,
+	C: ProvideRuntimeApi<Block>,
+	C: HeaderBackend<Block>,
+	C::Api: CanvasSystemRuntimeApi<Block>,
+{
+	fn remaining_weight(&self, at: Option<<Block as BlockT>::Hash>) -> Result<Weight> {
+		let api = self.client.runtime_api();
+		let at = at_or_best(&*self.client, at);
+
+		api.remaining_weight(&at).map_err(runtime_error_into_rpc_err)
+	}
+
+	fn decode_call(
+		&self,
+		extrinsic: Bytes,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Option<DecodedCall>> {
+		let api = self.client.runtime_api();
+		let at = at_or_best(&*self.client, at);
+
+		let summary = api.decode_call(&at, extrinsic.to_vec()).map_err(runtime_error_into_rpc_err)?;
+		Ok(summary.map(|summary| DecodedCall {
+			pallet: String::from_utf8_lossy(&summary.pallet).into_owned(),
+			call: String::from_utf8_lossy(&summary.call).into_owned(),
+			signed: summary.signed,
+			args_len: summary.args_len,
+		}))
+	}
+
+	fn pallet_versions(
+		&self,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<PalletStorageVersion>> {
+		let api = self.client.runtime_api();
+		let at = at_or_best(&*self.client, at);
+
+		let versions = api.pallet_versions(&at).map_err(runtime_error_into_rpc_err)?;
+		Ok(versions.into_iter()
+			.map(|(pallet, version)| PalletStorageVersion {
+				pallet: String::from_utf8_lossy(&pallet).into_owned(),
+				storage_version: version.map(|v| format!("{}.{}.{}", v.major, v.minor, v.patch)),
+			})
+			.collect())
+	}
+
+	fn call_allowed(&self, call: Bytes, at: Option<<Block as BlockT>::Hash>) -> Result<Option<bool>> {
+		let api = self.client.runtime_api();
+		let at = at_or_best(&*self.client, at);
+
+		api.call_allowed(&at, call.to_vec()).map_err(runtime_error_into_rpc_err)
+	}
+
+	fn batch_weight(&self, calls: Bytes, at: Option<<Block as BlockT>::Hash>) -> Result<Option<Weight>> {
+		let api = self.client.runtime_api();
+		let at = at_or_best(&*self.client, at);
+
+		api.batch_weight(&at, calls.to_vec()).map_err(runtime_error_into_rpc_err)
+	}
+
+	fn heights(&self) -> Result<Heights<NumberFor<Block>, <Block as BlockT>::Hash>> {
+		let info = self.client.info();
+		Ok(Heights {
+			best: info.best_number,
+			best_hash: info.best_hash,
+			finalized: info.finalized_number,
+			finalized_hash: info.finalized_hash,
+		})
+	}
+
+	fn next_expected_timestamp(&self, at: Option<<Block as BlockT>::Hash>) -> Result<u64> {
+		let api = self.client.runtime_api();
+		let at = at_or_best(&*self.client, at);
+
+		api.next_timestamp(&at).map_err(runtime_error_into_rpc_err)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_chain;
+	use canvas_runtime::opaque::Block;
+	use sc_client_api::Finalizer;
+	use sp_runtime::generic::BlockId;
+
+	#[test]
+	fn the_finalized_block_is_never_above_the_best_block() {
+		let client = test_chain::new_client();
+		let system = CanvasSystem::<_, Block>::new(client.clone());
+		let heights = system.heights().unwrap();
+		assert_eq!((heights.best, heights.finalized), (0, 0));
+
+		test_chain::import_blocks(&client, 3);
+		let heights = system.heights().unwrap();
+		assert_eq!((heights.best, heights.finalized), (3, 0));
+
+		client.finalize_block(BlockId::Number(2), None, true).unwrap();
+		let heights = system.heights().unwrap();
+		assert_eq!((heights.best, heights.finalized), (3, 2));
+		assert_eq!(Some(heights.best_hash), client.hash(3).unwrap());
+		assert_eq!(Some(heights.finalized_hash), client.hash(2).unwrap());
+	}
+}
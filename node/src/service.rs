@@ -1,6 +1,6 @@
 //! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
 
-use std::{sync::Arc, time::Duration};
+use std::{net::{Ipv4Addr, SocketAddr}, sync::Arc, time::Duration};
 use sc_client_api::{ExecutorProvider, RemoteBackend};
 use canvas_runtime::{self, opaque::Block, RuntimeApi};
 use sc_service::{error::Error as ServiceError, Configuration, TaskManager};
@@ -21,6 +21,23 @@ native_executor_instance!(
 	frame_benchmarking::benchmarking::HostFunctions,
 );
 
+/// Node specific configuration which is not covered by Substrate's `Configuration`.
+#[derive(Debug, Clone, Default)]
+pub struct CanvasConfig {
+	/// Port of the additional read-only contracts RPC server, if any.
+	pub contracts_rpc_port: Option<u16>,
+	/// Whether to log the contract activity of authored blocks.
+	pub log_contract_activity: bool,
+	/// The lowest `gas_limit` accepted by the `contracts_call` and `contracts_instantiate` RPCs.
+	pub contracts_min_gas_limit: u64,
+	/// The telemetry verbosity of the contract activity reports, `None` to disable them.
+	pub contracts_telemetry_verbosity: Option<u8>,
+	/// Port of the additional localhost RPC server which allows unsafe methods, if any.
+	pub contracts_unsafe_rpc_port: Option<u16>,
+	/// The number of transactions with a future nonce the pool buffers, `None` for the default.
+	pub pool_future_limit: Option<usize>,
+}
+
 type FullClient = sc_service::TFullClient<Block, RuntimeApi, Executor>;
 type FullBackend = sc_service::TFullBackend<Block>;
 type FullSelectChain = sc_consensus::LongestChain<FullBackend, Block>;
@@ -49,6 +66,11 @@ pub fn new_partial(config: &Configuration) -> Result<sc_service::PartialComponen
 		})
 		.transpose()?;
 
+	// Contract heavy workloads benefit from a larger cache, see `--state-cache-size`.
+	log::info!("💾 State cache size: {} bytes", config.state_cache_size);
+	// The runtime executes contracts, so this matters for contract heavy nodes, see `--wasm-execution`.
+	log::info!("⚙️  Wasm execution method: {:?}", config.wasm_method);
+
 	let (client, backend, keystore_container, task_manager) =
 		sc_service::new_full_parts::<Block, RuntimeApi, Executor>(
 			&config,
@@ -117,6 +139,39 @@ pub fn new_partial(config: &Configuration) -> Result<sc_service::PartialComponen
 	})
 }
 
+/// Applies `--pool-future-limit`, then validates and logs the limits of the transaction pool.
+fn configure_transaction_pool(
+	config: &mut Configuration,
+	future_limit: Option<usize>,
+) -> Result<(), ServiceError> {
+	if let Some(limit) = future_limit {
+		// More would let transactions which may never become ready crowd out ready ones.
+		let max = config.transaction_pool.ready.count;
+		if limit == 0 || limit > max {
+			return Err(ServiceError::Other(format!(
+				"`--pool-future-limit` must be between 1 and the pool limit of {}, got {}",
+				max,
+				limit,
+			)))
+		}
+		config.transaction_pool.future.count = limit;
+	}
+
+	// Contract spam fills the pool with pending calls, see `--pool-limit` and `--pool-kbytes`.
+	let pool_limit = &config.transaction_pool.ready;
+	if pool_limit.count == 0 || pool_limit.total_bytes == 0 {
+		return Err(ServiceError::Other(
+			"The transaction pool limits (`--pool-limit` and `--pool-kbytes`) must be positive".into()))
+	}
+	log::info!(
+		"📥 Transaction pool limits: {} transactions, {} kB, {} with a future nonce",
+		pool_limit.count,
+		pool_limit.total_bytes / 1024,
+		config.transaction_pool.future.count,
+	);
+	Ok(())
+}
+
 fn remote_keystore(_url: &String) -> Result<Arc<LocalKeystore>, &'static str> {
 	// FIXME: here would the concrete keystore be built,
 	//        must return a concrete type (NOT `LocalKeystore`) that
@@ -124,8 +179,62 @@ fn remote_keystore(_url: &String) -> Result<Arc<LocalKeystore>, &'static str> {
 	Err("Remote Keystore not supported.")
 }
 
+/// The browser origins the unsafe RPC server accepts, regardless of `--rpc-cors`.
+const LOCAL_ORIGINS: &[&str] = &[
+	"http://localhost:*",
+	"http://127.0.0.1:*",
+	"https://localhost:*",
+	"https://127.0.0.1:*",
+];
+
+/// The size limit of RPC requests without `--rpc-max-payload`, as in `sc_rpc_server`.
+const RPC_MAX_PAYLOAD_DEFAULT: usize = 15 * 1024 * 1024;
+
+/// Starts an additional HTTP RPC server on `addr` which serves `io`.
+///
+/// The server is set up like `sc_rpc_server` sets up the regular one: requests from a
+/// browser are only accepted from the `cors` origins and, unless all origins are allowed
+/// (`None`), only with a `Host` header naming the local machine, which prevents DNS
+/// rebinding. `max_payload` is the size limit of requests in MiB.
+fn start_http_rpc(
+	addr: &SocketAddr,
+	cors: Option<&Vec<String>>,
+	max_payload: Option<usize>,
+	io: jsonrpc_core::IoHandler<sc_rpc::Metadata>,
+) -> std::io::Result<jsonrpc_http_server::Server> {
+	use jsonrpc_http_server::{AccessControlAllowOrigin, DomainsValidation, Host, RestApi};
+
+	let (hosts, rest_api) = match cors {
+		Some(_) => (
+			DomainsValidation::AllowOnly(vec![Host::from("localhost"), Host::from("127.0.0.1")]),
+			RestApi::Secure,
+		),
+		None => (DomainsValidation::Disabled, RestApi::Unsecure),
+	};
+	let cors = cors
+		.map(|origins| origins.iter()
+			.map(|origin| AccessControlAllowOrigin::from(origin.as_str()))
+			.collect::<Vec<_>>())
+		.into();
+
+	jsonrpc_http_server::ServerBuilder::new(io)
+		.threads(4)
+		.allowed_hosts(hosts)
+		.cors(cors)
+		.rest_api(rest_api)
+		.max_request_body_size(
+			max_payload.map_or(RPC_MAX_PAYLOAD_DEFAULT, |mib| mib.saturating_mul(1024 * 1024)),
+		)
+		.start_http(addr)
+}
+
 /// Builds a new service for a full client.
-pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError> {
+pub fn new_full(
+	mut config: Configuration,
+	canvas_config: CanvasConfig,
+) -> Result<TaskManager, ServiceError> {
+	configure_transaction_pool(&mut config, canvas_config.pool_future_limit)?;
+
 	let sc_service::PartialComponents {
 		client,
 		backend,
@@ -172,19 +281,34 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 	let name = config.network.node_name.clone();
 	let enable_grandpa = !config.disable_grandpa;
 	let prometheus_registry = config.prometheus_registry().cloned();
+	let rpc_max_payload = config.rpc_max_payload;
+	let rpc_cors = config.rpc_cors.clone();
+	let min_gas_limit = canvas_config.contracts_min_gas_limit;
+
+	// The contracts query server listens on the same interface as the regular HTTP RPC server.
+	let contracts_rpc_addr = canvas_config.contracts_rpc_port.map(|port| {
+		let ip = config.rpc_http
+			.map(|addr| addr.ip())
+			.unwrap_or_else(|| Ipv4Addr::LOCALHOST.into());
+		SocketAddr::new(ip, port)
+	});
 
 	let rpc_extensions_builder = {
 		let client = client.clone();
 		let pool = transaction_pool.clone();
+		let prometheus_registry = prometheus_registry.clone();
 
-		Box::new(move |deny_unsafe, _| {
+		Box::new(move |deny_unsafe, subscription_executor| {
 			let deps = crate::rpc::FullDeps {
 				client: client.clone(),
 				pool: pool.clone(),
 				deny_unsafe,
+				subscription_executor,
+				min_gas_limit,
+				prometheus_registry: prometheus_registry.clone(),
 			};
 
-			crate::rpc::create_full(deps)
+			crate::rpc::create_full::<_, _, FullBackend>(deps)
 		})
 	};
 
@@ -205,7 +329,81 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		},
 	)?;
 
+	if let Some(addr) = contracts_rpc_addr {
+		let io = crate::rpc::create_contracts_query::<_, FullBackend>(
+			crate::rpc::ContractsQueryDeps {
+				client: client.clone(),
+				subscription_executor: sc_rpc::SubscriptionTaskExecutor::new(
+					task_manager.spawn_handle(),
+				),
+				rpc_max_payload,
+				min_gas_limit,
+			},
+		);
+
+		// Same origins and request size as the regular RPC server, see `--rpc-cors`.
+		let server = start_http_rpc(&addr, rpc_cors.as_ref(), rpc_max_payload, io)
+			.map_err(|e| ServiceError::Other(
+				format!("Unable to start the contracts RPC server on {}: {}", addr, e)))?;
+
+		log::info!("Contracts RPC server listening on {}", addr);
+		task_manager.keep_alive(server);
+	}
+
+	if let Some(port) = canvas_config.contracts_unsafe_rpc_port {
+		// Unsafe methods are only ever served to the local machine. Only the node specific
+		// methods of `create_full` are served, Substrate's own methods are not.
+		let addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), port);
+		let io = crate::rpc::create_full::<_, _, FullBackend>(crate::rpc::FullDeps {
+			client: client.clone(),
+			pool: transaction_pool.clone(),
+			deny_unsafe: crate::rpc::DenyUnsafe::No,
+			subscription_executor: sc_rpc::SubscriptionTaskExecutor::new(
+				task_manager.spawn_handle(),
+			),
+			min_gas_limit,
+			prometheus_registry: prometheus_registry.clone(),
+		});
+
+		// Only local pages may call it, `--rpc-cors all` would let any website do so.
+		let cors: Vec<_> = LOCAL_ORIGINS.iter().map(|origin| origin.to_string()).collect();
+		let server = start_http_rpc(&addr, Some(&cors), rpc_max_payload, io)
+			.map_err(|e| ServiceError::Other(
+				format!("Unable to start the unsafe contracts RPC server on {}: {}", addr, e)))?;
+
+		log::info!("Unsafe contracts RPC server listening on {}", addr);
+		task_manager.keep_alive(server);
+	}
+
+	if let (Some(verbosity), Some(telemetry)) =
+		(canvas_config.contracts_telemetry_verbosity, telemetry.as_ref())
+	{
+		task_manager.spawn_handle().spawn(
+			"contract-activity-telemetry",
+			crate::contract_activity::report_contract_activity::<_, FullBackend>(
+				client.clone(),
+				telemetry.handle(),
+				verbosity,
+			),
+		);
+	}
+
+	if let Some(registry) = prometheus_registry.as_ref() {
+		let metrics = crate::contract_activity::ContractActivityMetrics::register(registry)?;
+		task_manager.spawn_handle().spawn(
+			"contract-activity-metrics",
+			crate::contract_activity::export_contract_activity::<_, FullBackend>(client.clone(), metrics),
+		);
+	}
+
 	if role.is_authority() {
+		if canvas_config.log_contract_activity {
+			task_manager.spawn_handle().spawn(
+				"contract-activity-log",
+				crate::contract_activity::log_contract_activity::<_, FullBackend>(client.clone()),
+			);
+		}
+
 		let proposer_factory = sc_basic_authorship::ProposerFactory::new(
 			task_manager.spawn_handle(),
 			client.clone(),
@@ -303,7 +501,12 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 }
 
 /// Builds a new service for a light client.
-pub fn new_light(mut config: Configuration) -> Result<TaskManager, ServiceError> {
+pub fn new_light(
+	mut config: Configuration,
+	canvas_config: CanvasConfig,
+) -> Result<TaskManager, ServiceError> {
+	configure_transaction_pool(&mut config, canvas_config.pool_future_limit)?;
+
 	let telemetry = config.telemetry_endpoints.clone()
 		.filter(|x| !x.is_empty())
 		.map(|endpoints| -> Result<_, sc_telemetry::Error> {
@@ -0,0 +1,164 @@
+//! Test utilities: an in-memory client of the development chain.
+
+use std::{collections::HashMap, sync::Arc};
+use canvas_runtime::{
+	check_mortality::CheckMortality, opaque::Block, AccountId, Address, Balance, Call,
+	ContractsCall, MaxEraPeriod, RuntimeApi, SignedExtra, TimestampCall, UncheckedExtrinsic,
+	SLOT_DURATION, VERSION,
+};
+use codec::{Decode, Encode};
+use sc_block_builder::BlockBuilderProvider;
+use sc_client_api::in_mem;
+use sc_executor::{NativeExecutor, WasmExecutionMethod};
+use sc_service::client::{new_in_mem, Client, LocalCallExecutor};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_consensus::{BlockImport, BlockImportParams, BlockOrigin, ForkChoiceStrategy};
+use sp_consensus_aura::{Slot, AURA_ENGINE_ID};
+use sp_core::H256;
+use sp_keyring::AccountKeyring;
+use sp_runtime::{
+	generic::{BlockId, Digest, DigestItem, Era, SignedPayload},
+	traits::{BlakeTwo256, Block as BlockT, Get, Hash},
+};
+use substrate_frame_rpc_system::AccountNonceApi;
+
+use crate::{chain_spec, service::Executor};
+
+/// The backend of `TestClient`.
+pub type Backend = in_mem::Backend<Block>;
+
+/// A client of the development chain which keeps its state in memory.
+pub type TestClient = Client<
+	Backend,
+	LocalCallExecutor<Block, Backend, NativeExecutor<Executor>>,
+	Block,
+	RuntimeApi,
+>;
+
+/// Creates a client of the development chain at its genesis block.
+pub fn new_client() -> Arc<TestClient> {
+	let chain_spec = chain_spec::development_config().unwrap();
+	let client = new_in_mem(
+		NativeExecutor::<Executor>::new(WasmExecutionMethod::Interpreted, None, 8),
+		&chain_spec,
+		None,
+		None,
+		None,
+		Box::new(sp_core::testing::TaskExecutor::new()),
+		Default::default(),
+	)
+	.unwrap();
+	Arc::new(client)
+}
+
+/// Builds `count` blocks which only set the timestamp on top of the best block and
+/// imports them.
+pub fn import_blocks(client: &Arc<TestClient>, count: u32) {
+	for _ in 0..count {
+		import_block(client, vec![]);
+	}
+}
+
+/// Builds a block with `extrinsics` on top of the best block and imports it as the
+/// new best block. Returns its hash.
+///
+/// Block `n` is authored in Aura slot `n`, which its timestamp has to match.
+pub fn import_block(client: &Arc<TestClient>, extrinsics: Vec<UncheckedExtrinsic>) -> H256 {
+	let number = client.info().best_number + 1;
+	let slot = Slot::from(u64::from(number));
+	let digest = Digest {
+		logs: vec![DigestItem::PreRuntime(AURA_ENGINE_ID, slot.encode())],
+	};
+	let timestamp = Call::Timestamp(TimestampCall::set(u64::from(number) * SLOT_DURATION));
+
+	let mut builder = client.new_block(digest).unwrap();
+	for extrinsic in Some(UncheckedExtrinsic::new_unsigned(timestamp)).into_iter().chain(extrinsics) {
+		builder.push(Decode::decode(&mut &extrinsic.encode()[..]).unwrap()).unwrap();
+	}
+	let (header, body) = builder.build().unwrap().block.deconstruct();
+	let hash = header.hash();
+
+	let mut import = BlockImportParams::new(BlockOrigin::Own, header);
+	import.body = Some(body);
+	import.fork_choice = Some(ForkChoiceStrategy::LongestChain);
+	let mut importer = &**client;
+	futures::executor::block_on(importer.import_block(import, HashMap::new())).unwrap();
+	hash
+}
+
+/// Signs `call` by `signer` for the block on top of the best block, like a wallet does.
+pub fn sign(client: &Arc<TestClient>, signer: AccountKeyring, call: Call) -> UncheckedExtrinsic {
+	let info = client.info();
+	let who = signer.to_account_id();
+	let nonce = client
+		.runtime_api()
+		.account_nonce(&BlockId::Hash(info.best_hash), who.clone())
+		.unwrap();
+	let best = u64::from(info.best_number);
+	let era = Era::mortal(MaxEraPeriod::get(), best);
+	let era_start = client.hash(era.birth(best) as u32).unwrap().unwrap();
+
+	let extra: SignedExtra = (
+		frame_system::CheckSpecVersion::new(),
+		frame_system::CheckTxVersion::new(),
+		frame_system::CheckGenesis::new(),
+		CheckMortality::from(era),
+		frame_system::CheckNonce::from(nonce),
+		frame_system::CheckWeight::new(),
+		pallet_fees::ChargeTransactionPayment::from(0),
+		pallet_contracts_guard::CheckContractsCall::new(),
+	);
+	let additional = (
+		VERSION.spec_version,
+		VERSION.transaction_version,
+		info.genesis_hash,
+		era_start,
+		(),
+		(),
+		(),
+		(),
+	);
+	let payload = SignedPayload::from_raw(call, extra, additional);
+	let signature = payload.using_encoded(|payload| signer.sign(payload));
+	let (call, extra, _) = payload.deconstruct();
+	UncheckedExtrinsic::new_signed(call, Address::Id(who), signature.into(), extra)
+}
+
+/// A contract which does nothing when deployed or called.
+pub const DUMMY_CONTRACT: &str = r#"
+(module
+	(import "env" "memory" (memory 1 1))
+	(func (export "deploy"))
+	(func (export "call"))
+)
+"#;
+
+/// The gas limit of the contract calls used in the tests.
+pub const GAS_LIMIT: u64 = 10_000_000_000;
+
+/// Uploads and instantiates the contract given in the text format from `deployer` in a
+/// new block. Returns the address of the contract and the hash of its code.
+pub fn instantiate(
+	client: &Arc<TestClient>,
+	deployer: AccountKeyring,
+	wat: &str,
+	endowment: Balance,
+) -> (AccountId, H256) {
+	let code = wat::parse_str(wat).unwrap();
+	let code_hash = BlakeTwo256::hash(&code);
+	let call = Call::Contracts(ContractsCall::instantiate_with_code(
+		endowment,
+		GAS_LIMIT,
+		code,
+		vec![],
+		vec![],
+	));
+	import_block(client, vec![sign(client, deployer, call)]);
+	let address = pallet_contracts::Pallet::<canvas_runtime::Runtime>::contract_address(
+		&deployer.to_account_id(),
+		&code_hash,
+		&[],
+	);
+	(address, code_hash)
+}
@@ -0,0 +1,134 @@
+//! The `verify-range` subcommand.
+//!
+//! Re-executes a range of imported blocks on top of the state of their parents.
+//! `Executive::execute_block` checks the state root it computes against the one in
+//! the header, so the first block whose execution fails is where the node diverged.
+//! The state of every parent has to be available, i.e. the database must not have
+//! pruned it (see `--pruning archive`).
+
+use std::{sync::Arc, time::Instant};
+use canvas_runtime::opaque::Block;
+use log::info;
+use sc_cli::{CliConfiguration, DatabaseParams, PruningParams, SharedParams};
+use sc_client_api::BlockBackend;
+use sp_api::{Core, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::{Block as BlockT, Header as HeaderT}};
+use structopt::StructOpt;
+
+/// The `verify-range` command used to re-execute a range of blocks.
+#[derive(Debug, StructOpt)]
+pub struct VerifyRangeCmd {
+	/// Number of the first block to re-execute.
+	#[structopt(long, value_name = "NUMBER")]
+	pub from: u32,
+
+	/// Number of the last block to re-execute.
+	#[structopt(long, value_name = "NUMBER")]
+	pub to: u32,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl VerifyRangeCmd {
+	/// Run the verify-range command
+	pub async fn run<C>(&self, client: Arc<C>) -> sc_cli::Result<()>
+	where
+		C: BlockBackend<Block> + HeaderBackend<Block> + ProvideRuntimeApi<Block>,
+		C::Api: Core<Block>,
+	{
+		if self.from == 0 {
+			return Err("The genesis block can not be executed, start at block 1".into());
+		}
+		if self.from > self.to {
+			return Err(format!("--from {} is above --to {}", self.from, self.to).into());
+		}
+
+		info!("Re-executing blocks #{} to #{}...", self.from, self.to);
+		let started = Instant::now();
+
+		for number in self.from..=self.to {
+			let hash = client.hash(number)?
+				.ok_or_else(|| format!("Block #{} was not imported", number))?;
+			let block = client.block(&BlockId::Hash(hash))?
+				.ok_or_else(|| format!("The body of block #{} ({}) is not available", number, hash))?
+				.block;
+			let parent = BlockId::Hash(*block.header().parent_hash());
+
+			let block_started = Instant::now();
+			// Fails if the computed state root differs from the one in the header.
+			client.runtime_api().execute_block(&parent, block)
+				.map_err(|e| format!("Block #{} ({}) diverges: {:?}", number, hash, e))?;
+
+			println!("#{} {}: OK ({} ms)", number, hash, block_started.elapsed().as_millis());
+		}
+
+		info!(
+			"Re-executed {} blocks in {} ms.",
+			self.to - self.from + 1,
+			started.elapsed().as_millis(),
+		);
+		Ok(())
+	}
+}
+
+impl CliConfiguration for VerifyRangeCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_chain;
+
+	fn verify(client: &Arc<test_chain::TestClient>, from: u32, to: u32) -> sc_cli::Result<()> {
+		let cmd = VerifyRangeCmd::from_iter(&[
+			"verify-range",
+			"--from",
+			&from.to_string(),
+			"--to",
+			&to.to_string(),
+		]);
+		futures::executor::block_on(cmd.run(client.clone()))
+	}
+
+	#[test]
+	fn imported_blocks_are_verified() {
+		let client = test_chain::new_client();
+		test_chain::import_blocks(&client, 3);
+
+		assert!(verify(&client, 1, 3).is_ok());
+		assert!(verify(&client, 2, 2).is_ok());
+	}
+
+	#[test]
+	fn ranges_beyond_the_imported_blocks_are_rejected() {
+		let client = test_chain::new_client();
+		test_chain::import_blocks(&client, 3);
+
+		let error = verify(&client, 1, 4).unwrap_err().to_string();
+		assert!(error.contains("Block #4 was not imported"), "{}", error);
+		assert!(verify(&client, 0, 3).is_err());
+		assert!(verify(&client, 3, 2).is_err());
+	}
+}
@@ -0,0 +1,746 @@
+//! # Contracts Guard Pallet
+//!
+//! Enforces limits on the usage of the contracts pallet which `pallet_contracts`
+//! itself does not offer to configure. The limits are checked by the
+//! [`CheckContractsCall`] signed extension before a call into the contracts
+//! pallet is dispatched.
+//!
+//! ## Limits
+//!
+//! * `MaxInstantiationsPerBlock`: The number of `instantiate` and `instantiate_with_code`
+//!   calls which are included in a single block. Further instantiations are rejected with
+//!   `InvalidTransaction::ExhaustsResources`, so that they stay in the pool until the next block.
+//! * `MaxEndowment`: The highest endowment an instantiation may transfer to the new contract.
+//!   Instantiations above it are rejected with [`ENDOWMENT_TOO_HIGH`].
+//! * `MinEndowment`: The lowest endowment an instantiation may transfer to the new contract.
+//!   Instantiations below it are rejected with [`ENDOWMENT_TOO_LOW`]. Setting it to at least
+//!   the existential deposit rejects contracts which would be reaped right away.
+//! * `MaxSaltLength`: The longest salt an instantiation may use to derive the contract address.
+//!   Instantiations with a longer salt are rejected with [`SALT_TOO_LONG`].
+//! * `MaxContractsPerAccount`: The number of live contracts a single account may have
+//!   instantiated. Further instantiations are rejected with [`TOO_MANY_CONTRACTS`] until
+//!   one of the account's contracts is terminated (or evicted). Finding the live contracts
+//!   of an account reads up to `MaxContractsPerAccount` contracts, which is added to the
+//!   block weight of every instantiation.
+//! * `InstantiationCooldown`: The number of blocks an account has to wait after one of its
+//!   instantiations succeeded before it can instantiate again. Earlier instantiations are
+//!   rejected with [`INSTANTIATION_COOLDOWN`]. Zero disables the cooldown.
+//! * `MaxCallInputLength`: The longest `data` a `call` may pass to a contract in bytes.
+//!   Calls with longer input are rejected with [`INPUT_TOO_LONG`] before they execute.
+//! * `AllowCallValue`: Whether a `call` may transfer value to the called contract. If not,
+//!   calls with a nonzero `value` are rejected with [`VALUE_TRANSFER_DISABLED`].
+//! * `EnforceCodeWhitelist`: Whether only whitelisted code may be instantiated. If so,
+//!   `instantiate` and `instantiate_with_code` of code whose hash is not in the whitelist
+//!   are rejected with [`CODE_NOT_WHITELISTED`]. Root manages the whitelist with
+//!   `whitelist_code` and `remove_whitelisted_code`.
+//! * `BlockGasLimit`: The total gas which calls into the contracts pallet may use in a single
+//!   block, reserving the rest of the block weight for other extrinsics. A call whose `gas_limit`
+//!   exceeds it on its own is invalid and rejected with [`GAS_LIMIT_TOO_HIGH`]. A call whose
+//!   `gas_limit` does not fit into the current block anymore is rejected with
+//!   `InvalidTransaction::ExhaustsResources`, so that it stays in the pool. Unused gas is
+//!   given back to the block once the call is dispatched.
+//!
+//! ## Events
+//!
+//! After a call into the contracts pallet the extension emits `GasRefunded` if the
+//! transaction was charged for more weight (i.e. `gas_limit`) than it consumed.
+//!
+//! ## Penalties
+//!
+//! * `TrapSlash`: The fraction of the endowment which is slashed from the deployer if the
+//!   constructor of a contract traps. The slashed amount is handed to `OnTrapSlash`, the
+//!   rest of the endowment stays with the deployer as the instantiation is reverted.
+//!   Zero disables the penalty.
+//!
+//! ## Hooks
+//!
+//! * `OnContractCall`: Notified about every successfully dispatched `call` into a contract.
+//!   Calls made by contracts to other contracts are not dispatched, so they are not notified.
+//! * `OnInstantiation`: Notified about every successfully dispatched instantiation.
+//!
+//! The hooks run after the call was dispatched, their weight is added to the block weight
+//! when the call is checked.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use frame_support::{
+	traits::{Currency, Get, IsSubType, OnUnbalanced},
+	weights::{DispatchInfo, PostDispatchInfo, Weight},
+};
+use sp_core::crypto::UncheckedFrom;
+use sp_runtime::{
+	traits::{
+		DispatchInfoOf, Dispatchable, Hash, PostDispatchInfoOf, SignedExtension, StaticLookup,
+		Zero,
+	},
+	transaction_validity::{
+		InvalidTransaction, TransactionValidity, TransactionValidityError, ValidTransaction,
+	},
+	DispatchError, DispatchResult, RuntimeDebug,
+};
+use sp_std::{fmt, marker::PhantomData};
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+type BalanceOf<T> = <<T as pallet_contracts::Config>::Currency
+	as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+type NegativeImbalanceOf<T> = <<T as pallet_contracts::Config>::Currency
+	as Currency<<T as frame_system::Config>::AccountId>>::NegativeImbalance;
+
+/// Handler for successfully dispatched calls into contracts.
+///
+/// Only the `call`s dispatched by a signed extrinsic are handled, not the calls which
+/// contracts make into other contracts.
+pub trait OnContractCall<AccountId> {
+	/// Called after a `call` into the contract at `address` was dispatched successfully.
+	fn on_contract_call(address: &AccountId);
+
+	/// The weight of `on_contract_call`.
+	fn on_contract_call_weight() -> Weight;
+}
+
+impl<AccountId> OnContractCall<AccountId> for () {
+	fn on_contract_call(_: &AccountId) {}
+
+	fn on_contract_call_weight() -> Weight {
+		0
+	}
+}
+
+/// Handler for successfully dispatched instantiations.
+pub trait OnInstantiation<AccountId> {
+	/// Called after `deployer` instantiated the contract at `contract`.
+	fn on_instantiation(deployer: &AccountId, contract: &AccountId);
+
+	/// The weight of `on_instantiation`.
+	fn on_instantiation_weight() -> Weight;
+}
+
+impl<AccountId> OnInstantiation<AccountId> for () {
+	fn on_instantiation(_: &AccountId, _: &AccountId) {}
+
+	fn on_instantiation_weight() -> Weight {
+		0
+	}
+}
+
+/// The endowment of an instantiation exceeds `MaxEndowment`.
+pub const ENDOWMENT_TOO_HIGH: u8 = 110;
+
+/// The salt of an instantiation is longer than `MaxSaltLength`.
+pub const SALT_TOO_LONG: u8 = 111;
+
+/// A call transfers value to a contract although `AllowCallValue` is `false`.
+pub const VALUE_TRANSFER_DISABLED: u8 = 112;
+
+/// The deployer of an instantiation already owns `MaxContractsPerAccount` live contracts.
+pub const TOO_MANY_CONTRACTS: u8 = 113;
+
+/// The endowment of an instantiation is below `MinEndowment`.
+pub const ENDOWMENT_TOO_LOW: u8 = 114;
+
+/// The code of an instantiation is not whitelisted although `EnforceCodeWhitelist` is `true`.
+pub const CODE_NOT_WHITELISTED: u8 = 115;
+
+/// The deployer of an instantiation instantiated a contract less than
+/// `InstantiationCooldown` blocks ago.
+pub const INSTANTIATION_COOLDOWN: u8 = 116;
+
+/// The input data of a call is longer than `MaxCallInputLength`.
+pub const INPUT_TOO_LONG: u8 = 117;
+
+/// The gas limit of a call into the contracts pallet exceeds `BlockGasLimit`.
+pub const GAS_LIMIT_TOO_HIGH: u8 = 118;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+	use sp_runtime::Perbill;
+	use super::{BalanceOf, NegativeImbalanceOf, OnContractCall, OnInstantiation};
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config + pallet_contracts::Config {
+		/// The overarching event type.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The maximum number of contract instantiations in a single block.
+		#[pallet::constant]
+		type MaxInstantiationsPerBlock: Get<u32>;
+
+		/// The maximum endowment which can be transferred to a newly instantiated contract.
+		#[pallet::constant]
+		type MaxEndowment: Get<BalanceOf<Self>>;
+
+		/// The minimum endowment which must be transferred to a newly instantiated contract.
+		#[pallet::constant]
+		type MinEndowment: Get<BalanceOf<Self>>;
+
+		/// The maximum length of the salt of an instantiation in bytes.
+		#[pallet::constant]
+		type MaxSaltLength: Get<u32>;
+
+		/// The maximum length of the input data of a call into a contract in bytes.
+		#[pallet::constant]
+		type MaxCallInputLength: Get<u32>;
+
+		/// Whether calls into contracts may transfer value.
+		#[pallet::constant]
+		type AllowCallValue: Get<bool>;
+
+		/// The maximum number of live contracts instantiated by a single account.
+		#[pallet::constant]
+		type MaxContractsPerAccount: Get<u32>;
+
+		/// The minimum number of blocks between two instantiations by the same account.
+		#[pallet::constant]
+		type InstantiationCooldown: Get<Self::BlockNumber>;
+
+		/// Whether only code in `CodeWhitelist` can be instantiated.
+		#[pallet::constant]
+		type EnforceCodeWhitelist: Get<bool>;
+
+		/// The maximum gas which calls into the contracts pallet can use in a single block.
+		#[pallet::constant]
+		type BlockGasLimit: Get<Weight>;
+
+		/// Handler for successfully dispatched calls into contracts.
+		type OnContractCall: OnContractCall<Self::AccountId>;
+
+		/// Handler for successfully dispatched instantiations.
+		type OnInstantiation: OnInstantiation<Self::AccountId>;
+
+		/// The fraction of the endowment slashed if the constructor of a contract traps.
+		#[pallet::constant]
+		type TrapSlash: Get<Perbill>;
+
+		/// Handler for the endowments slashed because of trapped constructors.
+		type OnTrapSlash: OnUnbalanced<NegativeImbalanceOf<Self>>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(_n: T::BlockNumber) -> Weight {
+			InstantiationCount::<T>::kill();
+			BlockGas::<T>::kill();
+			T::DbWeight::get().writes(2)
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Allow the code with `code_hash` to be instantiated.
+		///
+		/// The dispatch origin for this call must be _Root_.
+		#[pallet::weight(T::DbWeight::get().writes(1))]
+		pub fn whitelist_code(origin: OriginFor<T>, code_hash: T::Hash) -> DispatchResult {
+			ensure_root(origin)?;
+			CodeWhitelist::<T>::insert(&code_hash, ());
+			Self::deposit_event(Event::CodeWhitelisted(code_hash));
+			Ok(())
+		}
+
+		/// Remove the code with `code_hash` from the whitelist.
+		///
+		/// Contracts which were already instantiated from the code are not affected.
+		/// The dispatch origin for this call must be _Root_.
+		#[pallet::weight(T::DbWeight::get().writes(1))]
+		pub fn remove_whitelisted_code(origin: OriginFor<T>, code_hash: T::Hash) -> DispatchResult {
+			ensure_root(origin)?;
+			CodeWhitelist::<T>::remove(&code_hash);
+			Self::deposit_event(Event::CodeRemovedFromWhitelist(code_hash));
+			Ok(())
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::metadata(T::AccountId = "AccountId", BalanceOf<T> = "Balance", T::Hash = "Hash")]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A call into the contracts pallet consumed less weight than it was charged
+		/// for and the difference was refunded. \[who, refunded\]
+		GasRefunded(T::AccountId, Weight),
+		/// Part of an endowment was slashed because the constructor of the contract
+		/// trapped. \[deployer, amount\]
+		EndowmentSlashed(T::AccountId, BalanceOf<T>),
+		/// Code was added to the whitelist. \[code_hash\]
+		CodeWhitelisted(T::Hash),
+		/// Code was removed from the whitelist. \[code_hash\]
+		CodeRemovedFromWhitelist(T::Hash),
+	}
+
+	/// The number of contract instantiations dispatched in the current block.
+	#[pallet::storage]
+	#[pallet::getter(fn instantiation_count)]
+	pub type InstantiationCount<T> = StorageValue<_, u32, ValueQuery>;
+
+	/// The gas used by calls into the contracts pallet in the current block.
+	///
+	/// Contains the full `gas_limit` of a call until it is dispatched.
+	#[pallet::storage]
+	#[pallet::getter(fn block_gas)]
+	pub type BlockGas<T> = StorageValue<_, Weight, ValueQuery>;
+
+	/// The block in which an account last instantiated a contract.
+	///
+	/// Only written while `InstantiationCooldown` is not zero.
+	#[pallet::storage]
+	pub type LastInstantiation<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, T::BlockNumber>;
+
+	/// The hashes of the code which can be instantiated while `EnforceCodeWhitelist` is `true`.
+	#[pallet::storage]
+	pub type CodeWhitelist<T: Config> = StorageMap<_, Identity, T::Hash, ()>;
+
+	/// The contracts instantiated by an account, keyed by deployer and contract address.
+	///
+	/// Entries of terminated contracts are only removed once their deployer instantiates
+	/// the next contract. As only deployers with less than `MaxContractsPerAccount` live
+	/// contracts can instantiate, a deployer has at most `MaxContractsPerAccount` entries.
+	#[pallet::storage]
+	pub type OwnedContracts<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		T::AccountId,
+		(),
+		ValueQuery,
+	>;
+}
+
+impl<T: Config> Pallet<T> {
+	/// Checks the parts of a call into the contracts pallet which do not depend on state.
+	fn check_call(call: &pallet_contracts::Call<T>) -> Result<(), TransactionValidityError> {
+		match call {
+			pallet_contracts::Call::instantiate_with_code(endowment, gas_limit, _, _, salt) |
+			pallet_contracts::Call::instantiate(endowment, gas_limit, _, _, salt) => {
+				Self::check_gas_limit(*gas_limit)?;
+				Self::check_endowment(endowment)?;
+				Self::check_salt(salt)
+			},
+			pallet_contracts::Call::call(_, value, gas_limit, data) => {
+				Self::check_gas_limit(*gas_limit)?;
+				Self::check_input(data)?;
+				Self::check_value(value)
+			},
+			_ => Ok(()),
+		}
+	}
+
+	/// Fails if `gas_limit` alone exceeds `BlockGasLimit`, i.e. if the call can never be included.
+	fn check_gas_limit(gas_limit: Weight) -> Result<(), TransactionValidityError> {
+		if gas_limit > T::BlockGasLimit::get() {
+			log::debug!(
+				target: "runtime::contracts-guard",
+				"Rejected contract call: gas limit {} exceeds the block gas limit of {}",
+				gas_limit,
+				T::BlockGasLimit::get(),
+			);
+			return Err(InvalidTransaction::Custom(GAS_LIMIT_TOO_HIGH).into());
+		}
+		Ok(())
+	}
+
+	/// Fails if `endowment` is not between `MinEndowment` and `MaxEndowment`.
+	fn check_endowment(endowment: &BalanceOf<T>) -> Result<(), TransactionValidityError> {
+		if *endowment < T::MinEndowment::get() {
+			log::debug!(
+				target: "runtime::contracts-guard",
+				"Rejected instantiation: endowment {:?} is below the minimum of {:?}",
+				endowment,
+				T::MinEndowment::get(),
+			);
+			return Err(InvalidTransaction::Custom(ENDOWMENT_TOO_LOW).into());
+		}
+		if *endowment > T::MaxEndowment::get() {
+			log::debug!(
+				target: "runtime::contracts-guard",
+				"Rejected instantiation: endowment {:?} exceeds the maximum of {:?}",
+				endowment,
+				T::MaxEndowment::get(),
+			);
+			return Err(InvalidTransaction::Custom(ENDOWMENT_TOO_HIGH).into());
+		}
+		Ok(())
+	}
+
+	/// Fails if `salt` is longer than `MaxSaltLength`.
+	fn check_salt(salt: &[u8]) -> Result<(), TransactionValidityError> {
+		if salt.len() > T::MaxSaltLength::get() as usize {
+			log::debug!(
+				target: "runtime::contracts-guard",
+				"Rejected instantiation: salt of {} bytes exceeds the maximum of {}",
+				salt.len(),
+				T::MaxSaltLength::get(),
+			);
+			return Err(InvalidTransaction::Custom(SALT_TOO_LONG).into());
+		}
+		Ok(())
+	}
+
+	/// Fails if `data` is longer than `MaxCallInputLength`.
+	fn check_input(data: &[u8]) -> Result<(), TransactionValidityError> {
+		if data.len() > T::MaxCallInputLength::get() as usize {
+			log::debug!(
+				target: "runtime::contracts-guard",
+				"Rejected call: input of {} bytes exceeds the maximum of {}",
+				data.len(),
+				T::MaxCallInputLength::get(),
+			);
+			return Err(InvalidTransaction::Custom(INPUT_TOO_LONG).into());
+		}
+		Ok(())
+	}
+
+	/// Fails if `value` is nonzero while `AllowCallValue` is `false`.
+	fn check_value(value: &BalanceOf<T>) -> Result<(), TransactionValidityError> {
+		if !value.is_zero() && !T::AllowCallValue::get() {
+			log::debug!(
+				target: "runtime::contracts-guard",
+				"Rejected call: transferring value to contracts is disabled",
+			);
+			return Err(InvalidTransaction::Custom(VALUE_TRANSFER_DISABLED).into());
+		}
+		Ok(())
+	}
+
+	/// Fails if `code_hash` is not whitelisted while `EnforceCodeWhitelist` is `true`.
+	fn check_code(code_hash: &T::Hash) -> Result<(), TransactionValidityError> {
+		if T::EnforceCodeWhitelist::get() && !CodeWhitelist::<T>::contains_key(code_hash) {
+			log::debug!(
+				target: "runtime::contracts-guard",
+				"Rejected instantiation: code {:?} is not whitelisted",
+				code_hash,
+			);
+			return Err(InvalidTransaction::Custom(CODE_NOT_WHITELISTED).into());
+		}
+		Ok(())
+	}
+
+	/// Fails if `deployer` instantiated a contract less than `InstantiationCooldown` blocks ago.
+	fn check_cooldown(deployer: &T::AccountId) -> Result<(), TransactionValidityError> {
+		let cooldown = T::InstantiationCooldown::get();
+		if cooldown.is_zero() {
+			return Ok(());
+		}
+		if let Some(last) = LastInstantiation::<T>::get(deployer) {
+			let now = frame_system::Pallet::<T>::block_number();
+			if now < last.saturating_add(cooldown) {
+				log::debug!(
+					target: "runtime::contracts-guard",
+					"Rejected instantiation: {:?} last instantiated in block {:?}, cooldown {:?}",
+					deployer,
+					last,
+					cooldown,
+				);
+				return Err(InvalidTransaction::Custom(INSTANTIATION_COOLDOWN).into());
+			}
+		}
+		Ok(())
+	}
+
+	/// Starts the cooldown of `deployer` after a successful instantiation.
+	fn note_cooldown(deployer: &T::AccountId) {
+		if !T::InstantiationCooldown::get().is_zero() {
+			LastInstantiation::<T>::insert(deployer, frame_system::Pallet::<T>::block_number());
+		}
+	}
+
+	/// Returns the number of contracts instantiated by `owner` which are still alive.
+	///
+	/// Only the first `MaxContractsPerAccount` contracts of `owner` are looked at, so the
+	/// result is at most `MaxContractsPerAccount`.
+	pub fn live_contracts(owner: &T::AccountId) -> u32 {
+		Self::owned_contracts(owner)
+			.filter(|contract| Self::is_alive(contract))
+			.count() as u32
+	}
+
+	/// The weight of the work done for an instantiation after it was dispatched.
+	fn instantiation_weight() -> Weight {
+		Self::owned_contracts_weight().saturating_add(T::OnInstantiation::on_instantiation_weight())
+	}
+
+	/// The weight of checking and recording the contracts owned by the deployer of an
+	/// instantiation, i.e. of `check_owned` and `note_owned`.
+	pub fn owned_contracts_weight() -> Weight {
+		let max = T::MaxContractsPerAccount::get() as Weight;
+		// Both read every entry of the deployer and the `ContractInfoOf` of its contract.
+		T::DbWeight::get().reads_writes(4 * max, max + 1)
+	}
+
+	fn owned_contracts(owner: &T::AccountId) -> impl Iterator<Item = T::AccountId> {
+		OwnedContracts::<T>::iter_prefix(owner)
+			.map(|(contract, _)| contract)
+			.take(T::MaxContractsPerAccount::get() as usize)
+	}
+
+	fn is_alive(contract: &T::AccountId) -> bool {
+		pallet_contracts::ContractInfoOf::<T>::get(contract)
+			.and_then(|info| info.get_alive())
+			.is_some()
+	}
+
+	/// Fails if `owner` already owns `MaxContractsPerAccount` live contracts.
+	fn check_owned(owner: &T::AccountId) -> Result<(), TransactionValidityError> {
+		let live = Self::live_contracts(owner);
+		if live >= T::MaxContractsPerAccount::get() {
+			log::debug!(
+				target: "runtime::contracts-guard",
+				"Rejected instantiation: {:?} already owns {} of at most {} contracts",
+				owner,
+				live,
+				T::MaxContractsPerAccount::get(),
+			);
+			return Err(InvalidTransaction::Custom(TOO_MANY_CONTRACTS).into());
+		}
+		Ok(())
+	}
+
+	/// Records `contract` as owned by `owner`, forgetting the owner's terminated contracts.
+	fn note_owned(owner: &T::AccountId, contract: T::AccountId) {
+		let dead: sp_std::vec::Vec<_> = Self::owned_contracts(owner)
+			.filter(|contract| !Self::is_alive(contract))
+			.collect();
+		for contract in dead {
+			OwnedContracts::<T>::remove(owner, contract);
+		}
+		OwnedContracts::<T>::insert(owner, contract, ());
+	}
+
+	/// Reserves `gas_limit` of the gas available to contracts in the current block.
+	///
+	/// Fails if this would exceed `BlockGasLimit`.
+	fn note_gas_limit(gas_limit: Weight) -> Result<(), TransactionValidityError> {
+		BlockGas::<T>::try_mutate(|gas| {
+			let total = gas.saturating_add(gas_limit);
+			if total > T::BlockGasLimit::get() {
+				log::debug!(
+					target: "runtime::contracts-guard",
+					"Rejected contract call: gas limit {} exceeds the remaining block gas of {}",
+					gas_limit,
+					T::BlockGasLimit::get().saturating_sub(*gas),
+				);
+				return Err(InvalidTransaction::ExhaustsResources.into());
+			}
+			*gas = total;
+			Ok(())
+		})
+	}
+
+	/// Slashes `TrapSlash` of `endowment` from `deployer` if `result` is a trapped constructor.
+	fn slash_trapped(deployer: &T::AccountId, endowment: BalanceOf<T>, result: &DispatchResult) {
+		let trapped: DispatchError = pallet_contracts::Error::<T>::ContractTrapped.into();
+		if *result != Err(trapped) {
+			return;
+		}
+		let amount = T::TrapSlash::get() * endowment;
+		if amount.is_zero() {
+			return;
+		}
+		let (imbalance, _) = T::Currency::slash(deployer, amount);
+		T::OnTrapSlash::on_unbalanced(imbalance);
+		Self::deposit_event(Event::EndowmentSlashed(deployer.clone(), amount));
+	}
+
+	/// Accounts for an instantiation in the current block.
+	///
+	/// Fails if the block already contains `MaxInstantiationsPerBlock` instantiations.
+	fn note_instantiation() -> Result<(), TransactionValidityError> {
+		InstantiationCount::<T>::try_mutate(|count| {
+			if *count >= T::MaxInstantiationsPerBlock::get() {
+				log::debug!(
+					target: "runtime::contracts-guard",
+					"Rejected instantiation: limit of {} instantiations per block reached",
+					count,
+				);
+				return Err(InvalidTransaction::ExhaustsResources.into());
+			}
+			*count += 1;
+			Ok(())
+		})
+	}
+}
+
+/// What [`CheckContractsCall`] remembers about a call into the contracts pallet until
+/// it is dispatched.
+#[derive(RuntimeDebug)]
+pub struct PreDispatch<AccountId, Balance> {
+	/// The signer of the call.
+	pub who: AccountId,
+	/// The called contract if this is a `call`.
+	pub dest: Option<AccountId>,
+	/// The endowment if this is an instantiation.
+	pub endowment: Option<Balance>,
+	/// The address of the contract if this is an instantiation.
+	pub contract: Option<AccountId>,
+}
+
+/// Checks calls into the contracts pallet against the limits of this pallet.
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct CheckContractsCall<T: Config + Send + Sync>(PhantomData<T>);
+
+impl<T: Config + Send + Sync> CheckContractsCall<T> {
+	/// Create new `SignedExtension` to check calls into the contracts pallet.
+	pub fn new() -> Self {
+		Self(PhantomData)
+	}
+}
+
+impl<T: Config + Send + Sync> Default for CheckContractsCall<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T: Config + Send + Sync> fmt::Debug for CheckContractsCall<T> {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "CheckContractsCall")
+	}
+
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut fmt::Formatter) -> fmt::Result {
+		Ok(())
+	}
+}
+
+impl<T: Config + Send + Sync> SignedExtension for CheckContractsCall<T> where
+	<T as frame_system::Config>::Call: IsSubType<pallet_contracts::Call<T>>,
+	<T as frame_system::Config>::Call: Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo>,
+	T::AccountId: UncheckedFrom<T::Hash> + AsRef<[u8]>,
+{
+	const IDENTIFIER: &'static str = "CheckContractsCall";
+	type AccountId = T::AccountId;
+	type Call = <T as frame_system::Config>::Call;
+	type AdditionalSigned = ();
+	/// `None` for calls which are not into the contracts pallet.
+	type Pre = Option<PreDispatch<Self::AccountId, BalanceOf<T>>>;
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+
+	fn validate(
+		&self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		_info: &DispatchInfoOf<Self::Call>,
+		_len: usize,
+	) -> TransactionValidity {
+		if let Some(call) = call.is_sub_type() {
+			Pallet::<T>::check_call(call)?;
+			match call {
+				pallet_contracts::Call::instantiate_with_code(_, _, code, ..) => {
+					Pallet::<T>::check_code(&T::Hashing::hash(code))?;
+					Pallet::<T>::check_owned(who)?;
+					Pallet::<T>::check_cooldown(who)?;
+				},
+				pallet_contracts::Call::instantiate(_, _, code_hash, ..) => {
+					Pallet::<T>::check_code(code_hash)?;
+					Pallet::<T>::check_owned(who)?;
+					Pallet::<T>::check_cooldown(who)?;
+				},
+				_ => {},
+			}
+		}
+		Ok(ValidTransaction::default())
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		_len: usize,
+	) -> Result<Self::Pre, TransactionValidityError> {
+		let call = match call.is_sub_type() {
+			Some(call) => call,
+			None => return Ok(None),
+		};
+		Pallet::<T>::check_call(call)?;
+		let mut pre = PreDispatch { who: who.clone(), dest: None, endowment: None, contract: None };
+		match call {
+			pallet_contracts::Call::instantiate_with_code(endowment, gas_limit, code, _, salt) => {
+				let code_hash = T::Hashing::hash(code);
+				Pallet::<T>::check_code(&code_hash)?;
+				Pallet::<T>::check_owned(who)?;
+				Pallet::<T>::check_cooldown(who)?;
+				Pallet::<T>::note_instantiation()?;
+				Pallet::<T>::note_gas_limit(*gas_limit)?;
+				frame_system::Pallet::<T>::register_extra_weight_unchecked(
+					Pallet::<T>::instantiation_weight(),
+					info.class,
+				);
+				pre.endowment = Some(*endowment);
+				pre.contract = Some(
+					pallet_contracts::Pallet::<T>::contract_address(who, &code_hash, salt),
+				);
+			},
+			pallet_contracts::Call::instantiate(endowment, gas_limit, code_hash, _, salt) => {
+				Pallet::<T>::check_code(code_hash)?;
+				Pallet::<T>::check_owned(who)?;
+				Pallet::<T>::check_cooldown(who)?;
+				Pallet::<T>::note_instantiation()?;
+				Pallet::<T>::note_gas_limit(*gas_limit)?;
+				frame_system::Pallet::<T>::register_extra_weight_unchecked(
+					Pallet::<T>::instantiation_weight(),
+					info.class,
+				);
+				pre.endowment = Some(*endowment);
+				pre.contract = Some(
+					pallet_contracts::Pallet::<T>::contract_address(who, code_hash, salt),
+				);
+			},
+			pallet_contracts::Call::call(dest, _, gas_limit, _) => {
+				Pallet::<T>::note_gas_limit(*gas_limit)?;
+				frame_system::Pallet::<T>::register_extra_weight_unchecked(
+					T::OnContractCall::on_contract_call_weight(),
+					info.class,
+				);
+				pre.dest = T::Lookup::lookup(dest.clone()).ok();
+			},
+			_ => {},
+		}
+		Ok(Some(pre))
+	}
+
+	fn post_dispatch(
+		pre: Self::Pre,
+		info: &DispatchInfoOf<Self::Call>,
+		post_info: &PostDispatchInfoOf<Self::Call>,
+		_len: usize,
+		result: &DispatchResult,
+	) -> Result<(), TransactionValidityError> {
+		if let Some(PreDispatch { who, dest, endowment, contract }) = pre {
+			if let (Some(dest), Ok(())) = (dest, result) {
+				T::OnContractCall::on_contract_call(&dest);
+			}
+			if let (Some(contract), Ok(())) = (contract, result) {
+				T::OnInstantiation::on_instantiation(&who, &contract);
+				Pallet::<T>::note_cooldown(&who);
+				Pallet::<T>::note_owned(&who, contract);
+			}
+			if let Some(endowment) = endowment {
+				Pallet::<T>::slash_trapped(&who, endowment, result);
+			}
+			let refunded = info.weight.saturating_sub(post_info.calc_actual_weight(info));
+			if !refunded.is_zero() {
+				BlockGas::<T>::mutate(|gas| *gas = gas.saturating_sub(refunded));
+				Pallet::<T>::deposit_event(Event::GasRefunded(who, refunded));
+			}
+		}
+		Ok(())
+	}
+}
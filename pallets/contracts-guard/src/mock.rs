@@ -0,0 +1,292 @@
+//! Test utilities
+
+use crate as pallet_contracts_guard;
+use crate::CheckContractsCall;
+use frame_support::{
+	parameter_types,
+	traits::{Currency, GenesisBuild, OnInitialize, OnUnbalanced},
+	weights::{GetDispatchInfo, RuntimeDbWeight, Weight},
+};
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, Convert, Dispatchable, Hash, IdentityLookup, SignedExtension},
+	transaction_validity::{TransactionValidity, TransactionValidityError},
+	AccountId32, DispatchResult, Perbill,
+};
+
+pub type AccountId = AccountId32;
+pub type Balance = u64;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+		Timestamp: pallet_timestamp::{Pallet, Call, Storage, Inherent},
+		Randomness: pallet_randomness_collective_flip::{Pallet, Storage},
+		Contracts: pallet_contracts::{Pallet, Call, Storage, Event<T>},
+		ContractsGuard: pallet_contracts_guard::{Pallet, Call, Storage, Event<T>},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub BlockWeights: frame_system::limits::BlockWeights =
+		frame_system::limits::BlockWeights::simple_max(2 * 1_000_000_000_000);
+	// Small enough not to change the gas used by contracts, but not zero so that the
+	// weight of the storage accesses of this pallet can be checked.
+	pub const DbWeight: RuntimeDbWeight = RuntimeDbWeight { read: 1, write: 2 };
+}
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = ();
+	type BlockWeights = BlockWeights;
+	type BlockLength = ();
+	type DbWeight = DbWeight;
+	type Origin = Origin;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Call = Call;
+	type Hashing = BlakeTwo256;
+	type AccountId = AccountId;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<Balance>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ();
+	type OnSetCode = ();
+}
+
+parameter_types! {
+	pub const ExistentialDeposit: Balance = 1;
+}
+
+impl pallet_balances::Config for Test {
+	type MaxLocks = ();
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+	type Balance = Balance;
+	type Event = Event;
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const MinimumPeriod: u64 = 1;
+}
+
+impl pallet_timestamp::Config for Test {
+	type Moment = u64;
+	type OnTimestampSet = ();
+	type MinimumPeriod = MinimumPeriod;
+	type WeightInfo = ();
+}
+
+impl pallet_randomness_collective_flip::Config for Test {}
+
+parameter_types! {
+	pub const SignedClaimHandicap: u64 = 2;
+	pub const TombstoneDeposit: Balance = 16;
+	pub const DepositPerContract: Balance = 80_000;
+	pub const DepositPerStorageByte: Balance = 10_000;
+	pub const DepositPerStorageItem: Balance = 10_000;
+	pub RentFraction: Perbill = Perbill::from_rational(4u32, 10_000u32);
+	pub const SurchargeReward: Balance = 500_000;
+	pub const DeletionQueueDepth: u32 = 1024;
+	pub const DeletionWeightLimit: Weight = 500_000_000_000;
+	pub MySchedule: pallet_contracts::Schedule<Test> = Default::default();
+}
+
+impl Convert<Weight, Balance> for Test {
+	fn convert(w: Weight) -> Balance {
+		w
+	}
+}
+
+impl pallet_contracts::Config for Test {
+	type Time = Timestamp;
+	type Randomness = Randomness;
+	type Currency = Balances;
+	type Event = Event;
+	type RentPayment = ();
+	type SignedClaimHandicap = SignedClaimHandicap;
+	type TombstoneDeposit = TombstoneDeposit;
+	type DepositPerContract = DepositPerContract;
+	type DepositPerStorageByte = DepositPerStorageByte;
+	type DepositPerStorageItem = DepositPerStorageItem;
+	type RentFraction = RentFraction;
+	type SurchargeReward = SurchargeReward;
+	type WeightPrice = Self;
+	type WeightInfo = ();
+	type ChainExtension = ();
+	type DeletionQueueDepth = DeletionQueueDepth;
+	type DeletionWeightLimit = DeletionWeightLimit;
+	type Schedule = MySchedule;
+	type CallStack = [pallet_contracts::Frame<Self>; 31];
+}
+
+parameter_types! {
+	pub storage MaxInstantiationsPerBlock: u32 = 2;
+	pub storage MaxEndowment: Balance = 1_000_000;
+	pub storage MinEndowment: Balance = 1_000;
+	pub storage MaxSaltLength: u32 = 4;
+	pub storage MaxCallInputLength: u32 = 8;
+	pub storage AllowCallValue: bool = true;
+	pub storage MaxContractsPerAccount: u32 = 2;
+	pub storage InstantiationCooldown: u64 = 0;
+	pub storage EnforceCodeWhitelist: bool = false;
+	pub storage BlockGasLimit: Weight = 3 * GAS_LIMIT;
+	pub storage TrapSlash: Perbill = Perbill::zero();
+}
+
+impl pallet_contracts_guard::Config for Test {
+	type Event = Event;
+	type MaxInstantiationsPerBlock = MaxInstantiationsPerBlock;
+	type MaxEndowment = MaxEndowment;
+	type MinEndowment = MinEndowment;
+	type MaxSaltLength = MaxSaltLength;
+	type MaxCallInputLength = MaxCallInputLength;
+	type AllowCallValue = AllowCallValue;
+	type MaxContractsPerAccount = MaxContractsPerAccount;
+	type InstantiationCooldown = InstantiationCooldown;
+	type EnforceCodeWhitelist = EnforceCodeWhitelist;
+	type BlockGasLimit = BlockGasLimit;
+	type OnContractCall = ();
+	type OnInstantiation = ();
+	type TrapSlash = TrapSlash;
+	type OnTrapSlash = DepositToTreasury;
+}
+
+/// Deposits slashed endowments into `TREASURY`.
+pub struct DepositToTreasury;
+
+impl OnUnbalanced<pallet_balances::NegativeImbalance<Test>> for DepositToTreasury {
+	fn on_nonzero_unbalanced(amount: pallet_balances::NegativeImbalance<Test>) {
+		Balances::resolve_creating(&TREASURY, amount);
+	}
+}
+
+pub const ALICE: AccountId = AccountId32::new([1u8; 32]);
+pub const BOB: AccountId = AccountId32::new([2u8; 32]);
+pub const TREASURY: AccountId = AccountId32::new([3u8; 32]);
+
+pub const GAS_LIMIT: Weight = 10_000_000_000;
+pub const ENDOWMENT: Balance = 100_000;
+
+/// A contract which does nothing but can be deployed and called.
+pub const DUMMY: &str = r#"
+(module
+	(import "env" "memory" (memory 1 1))
+	(func (export "deploy"))
+	(func (export "call"))
+)
+"#;
+
+/// A contract whose constructor traps.
+pub const TRAP_IN_DEPLOY: &str = r#"
+(module
+	(import "env" "memory" (memory 1 1))
+	(func (export "deploy") unreachable)
+	(func (export "call"))
+)
+"#;
+
+/// A contract which terminates itself when called, sending its balance to the caller.
+pub const SELF_DESTRUCT: &str = r#"
+(module
+	(import "seal0" "seal_caller" (func $seal_caller (param i32 i32)))
+	(import "seal0" "seal_terminate" (func $seal_terminate (param i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	;; [0, 4) the length of the caller's address
+	(data (i32.const 0) "\20")
+
+	(func (export "deploy"))
+	(func (export "call")
+		;; [4, 36) the caller's address
+		(call $seal_caller (i32.const 4) (i32.const 0))
+		(call $seal_terminate (i32.const 4) (i32.const 32))
+	)
+)
+"#;
+
+pub fn wasm(wat: &str) -> Vec<u8> {
+	wat::parse_str(wat).unwrap()
+}
+
+pub fn code_hash(wat: &str) -> H256 {
+	BlakeTwo256::hash(&wasm(wat))
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(ALICE, 1_000_000_000_000), (BOB, 1_000_000_000_000)],
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+/// Starts the next block, which resets the per block limits.
+pub fn next_block() {
+	System::set_block_number(System::block_number() + 1);
+	ContractsGuard::on_initialize(System::block_number());
+}
+
+/// Validates `call` signed by `who` with the guard and dispatches it if it is valid.
+///
+/// Returns the outcome of the dispatch.
+pub fn submit(who: &AccountId, call: Call) -> Result<DispatchResult, TransactionValidityError> {
+	let info = call.get_dispatch_info();
+	let extension = CheckContractsCall::<Test>::default();
+	extension.validate(who, &call, &info, 0)?;
+	let pre = extension.pre_dispatch(who, &call, &info, 0)?;
+	let (post_info, result) = match call.dispatch(Origin::signed(who.clone())) {
+		Ok(post_info) => (post_info, Ok(())),
+		Err(e) => (e.post_info, Err(e.error)),
+	};
+	CheckContractsCall::<Test>::post_dispatch(pre, &info, &post_info, 0, &result)?;
+	Ok(result)
+}
+
+/// Only validates `call` signed by `who`, as the transaction pool does.
+pub fn validate(who: &AccountId, call: &Call) -> TransactionValidity {
+	CheckContractsCall::<Test>::default().validate(who, call, &call.get_dispatch_info(), 0)
+}
+
+pub fn instantiate_with_code(wat: &str, endowment: Balance, salt: Vec<u8>) -> Call {
+	Call::Contracts(pallet_contracts::Call::instantiate_with_code(
+		endowment,
+		GAS_LIMIT,
+		wasm(wat),
+		vec![],
+		salt,
+	))
+}
+
+pub fn call(dest: &AccountId, value: Balance, data: Vec<u8>) -> Call {
+	Call::Contracts(pallet_contracts::Call::call(dest.clone(), value, GAS_LIMIT, data))
+}
+
+pub fn contract_address(deployer: &AccountId, wat: &str, salt: &[u8]) -> AccountId {
+	Contracts::contract_address(deployer, &code_hash(wat), salt)
+}
@@ -0,0 +1,301 @@
+//! Tests for the contracts guard pallet.
+
+use super::*;
+use crate::mock::*;
+use frame_support::assert_ok;
+use sp_runtime::{
+	transaction_validity::{InvalidTransaction, TransactionValidityError},
+	Perbill,
+};
+
+#[test]
+fn instantiations_are_limited_per_block() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(MaxInstantiationsPerBlock::get(), 2);
+		assert_eq!(submit(&ALICE, instantiate_with_code(DUMMY, ENDOWMENT, vec![1])), Ok(Ok(())));
+		assert_eq!(submit(&ALICE, instantiate_with_code(DUMMY, ENDOWMENT, vec![2])), Ok(Ok(())));
+		assert_eq!(ContractsGuard::instantiation_count(), 2);
+
+		assert_eq!(
+			submit(&BOB, instantiate_with_code(DUMMY, ENDOWMENT, vec![3])),
+			Err(InvalidTransaction::ExhaustsResources.into()),
+		);
+
+		next_block();
+		assert_eq!(ContractsGuard::instantiation_count(), 0);
+		assert_eq!(submit(&BOB, instantiate_with_code(DUMMY, ENDOWMENT, vec![3])), Ok(Ok(())));
+	});
+}
+
+#[test]
+fn calls_are_not_counted_as_instantiations() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(submit(&ALICE, instantiate_with_code(DUMMY, ENDOWMENT, vec![])), Ok(Ok(())));
+		let dest = contract_address(&ALICE, DUMMY, &[]);
+		assert_eq!(submit(&ALICE, call(&dest, 0, vec![])), Ok(Ok(())));
+		assert_eq!(ContractsGuard::instantiation_count(), 1);
+	});
+}
+
+#[test]
+fn unused_gas_is_refunded_with_an_event() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(submit(&ALICE, instantiate_with_code(DUMMY, ENDOWMENT, vec![])), Ok(Ok(())));
+		let dest = contract_address(&ALICE, DUMMY, &[]);
+		let gas_consumed = Contracts::bare_call(ALICE, dest.clone(), 0, GAS_LIMIT, vec![], false)
+			.gas_consumed;
+		assert!(gas_consumed < GAS_LIMIT);
+
+		System::reset_events();
+		assert_eq!(submit(&BOB, call(&dest, 0, vec![])), Ok(Ok(())));
+		assert_eq!(
+			System::events().last().map(|r| r.event.clone()),
+			Some(Event::ContractsGuard(crate::Event::GasRefunded(BOB, GAS_LIMIT - gas_consumed))),
+		);
+	});
+}
+
+#[test]
+fn endowments_above_max_endowment_are_rejected() {
+	new_test_ext().execute_with(|| {
+		let max = MaxEndowment::get();
+		assert_eq!(
+			submit(&ALICE, instantiate_with_code(DUMMY, max + 1, vec![])),
+			Err(InvalidTransaction::Custom(ENDOWMENT_TOO_HIGH).into()),
+		);
+		assert_eq!(submit(&ALICE, instantiate_with_code(DUMMY, max, vec![])), Ok(Ok(())));
+		assert_eq!(Balances::free_balance(contract_address(&ALICE, DUMMY, &[])), max);
+
+		let instantiate = |endowment| Call::Contracts(pallet_contracts::Call::instantiate(
+			endowment,
+			GAS_LIMIT,
+			code_hash(DUMMY),
+			vec![],
+			vec![1],
+		));
+		assert_eq!(
+			validate(&BOB, &instantiate(max + 1)),
+			Err(InvalidTransaction::Custom(ENDOWMENT_TOO_HIGH).into()),
+		);
+		assert!(validate(&BOB, &instantiate(max)).is_ok());
+	});
+}
+
+#[test]
+fn contract_gas_is_limited_per_block() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(submit(&ALICE, instantiate_with_code(DUMMY, ENDOWMENT, vec![])), Ok(Ok(())));
+		let dest = contract_address(&ALICE, DUMMY, &[]);
+		// Only the gas which was actually used stays reserved.
+		assert!(ContractsGuard::block_gas() < GAS_LIMIT);
+
+		BlockGas::<Test>::put(BlockGasLimit::get() - GAS_LIMIT);
+		assert_eq!(submit(&ALICE, call(&dest, 0, vec![])), Ok(Ok(())));
+		assert_eq!(
+			submit(&BOB, call(&dest, 0, vec![])),
+			Err(InvalidTransaction::ExhaustsResources.into()),
+		);
+		// The call is still valid, it just has to wait for the next block.
+		assert!(validate(&BOB, &call(&dest, 0, vec![])).is_ok());
+
+		next_block();
+		assert_eq!(ContractsGuard::block_gas(), 0);
+		assert_eq!(submit(&BOB, call(&dest, 0, vec![])), Ok(Ok(())));
+	});
+}
+
+#[test]
+fn gas_limits_above_the_block_gas_limit_are_invalid() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(submit(&ALICE, instantiate_with_code(DUMMY, ENDOWMENT, vec![])), Ok(Ok(())));
+		let dest = contract_address(&ALICE, DUMMY, &[]);
+		let too_high: TransactionValidityError = InvalidTransaction::Custom(GAS_LIMIT_TOO_HIGH).into();
+
+		let call = |gas_limit| {
+			Call::Contracts(pallet_contracts::Call::call(dest.clone(), 0, gas_limit, vec![]))
+		};
+		assert!(validate(&ALICE, &call(BlockGasLimit::get())).is_ok());
+		assert_eq!(validate(&ALICE, &call(BlockGasLimit::get() + 1)), Err(too_high));
+
+		let instantiate = |gas_limit| Call::Contracts(pallet_contracts::Call::instantiate(
+			ENDOWMENT,
+			gas_limit,
+			code_hash(DUMMY),
+			vec![],
+			vec![1],
+		));
+		assert_eq!(validate(&ALICE, &instantiate(BlockGasLimit::get() + 1)), Err(too_high));
+		assert_eq!(submit(&ALICE, instantiate(BlockGasLimit::get() + 1)), Err(too_high));
+	});
+}
+
+#[test]
+fn trapped_constructors_slash_the_trap_slash_share_of_the_endowment() {
+	new_test_ext().execute_with(|| {
+		TrapSlash::set(&Perbill::from_percent(10));
+		let balance = Balances::free_balance(&ALICE);
+
+		assert_eq!(
+			submit(&ALICE, instantiate_with_code(TRAP_IN_DEPLOY, ENDOWMENT, vec![])),
+			Ok(Err(pallet_contracts::Error::<Test>::ContractTrapped.into())),
+		);
+		assert_eq!(Balances::free_balance(&ALICE), balance - ENDOWMENT / 10);
+		assert_eq!(Balances::free_balance(&TREASURY), ENDOWMENT / 10);
+		assert_eq!(
+			System::events().last().map(|r| r.event.clone()),
+			Some(Event::ContractsGuard(crate::Event::EndowmentSlashed(ALICE, ENDOWMENT / 10))),
+		);
+
+		// Constructors which do not trap are not slashed.
+		let balance = Balances::free_balance(&ALICE);
+		assert_eq!(submit(&ALICE, instantiate_with_code(DUMMY, ENDOWMENT, vec![])), Ok(Ok(())));
+		assert_eq!(Balances::free_balance(&ALICE), balance - ENDOWMENT);
+		assert_eq!(Balances::free_balance(&TREASURY), ENDOWMENT / 10);
+	});
+}
+
+#[test]
+fn contracts_are_limited_per_account() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(MaxContractsPerAccount::get(), 2);
+		assert_eq!(submit(&ALICE, instantiate_with_code(DUMMY, ENDOWMENT, vec![1])), Ok(Ok(())));
+		assert_eq!(submit(&ALICE, instantiate_with_code(DUMMY, ENDOWMENT, vec![2])), Ok(Ok(())));
+		assert_eq!(ContractsGuard::live_contracts(&ALICE), 2);
+
+		next_block();
+		let too_many: TransactionValidityError = InvalidTransaction::Custom(TOO_MANY_CONTRACTS).into();
+		assert_eq!(validate(&ALICE, &instantiate_with_code(DUMMY, ENDOWMENT, vec![3])), Err(too_many));
+		assert_eq!(submit(&ALICE, instantiate_with_code(DUMMY, ENDOWMENT, vec![3])), Err(too_many));
+		// Other deployers are not affected.
+		assert_eq!(submit(&BOB, instantiate_with_code(DUMMY, ENDOWMENT, vec![3])), Ok(Ok(())));
+	});
+}
+
+#[test]
+fn terminated_contracts_free_their_slot() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(submit(&ALICE, instantiate_with_code(DUMMY, ENDOWMENT, vec![])), Ok(Ok(())));
+		assert_eq!(submit(&ALICE, instantiate_with_code(SELF_DESTRUCT, ENDOWMENT, vec![])), Ok(Ok(())));
+
+		next_block();
+		let too_many: TransactionValidityError = InvalidTransaction::Custom(TOO_MANY_CONTRACTS).into();
+		assert_eq!(submit(&ALICE, instantiate_with_code(DUMMY, ENDOWMENT, vec![1])), Err(too_many));
+
+		let self_destruct = contract_address(&ALICE, SELF_DESTRUCT, &[]);
+		assert_eq!(submit(&BOB, call(&self_destruct, 0, vec![])), Ok(Ok(())));
+		assert_eq!(ContractsGuard::live_contracts(&ALICE), 1);
+		assert_eq!(submit(&ALICE, instantiate_with_code(DUMMY, ENDOWMENT, vec![1])), Ok(Ok(())));
+		assert_eq!(ContractsGuard::live_contracts(&ALICE), 2);
+	});
+}
+
+#[test]
+fn instantiations_are_charged_for_reading_the_owned_contracts() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(ContractsGuard::owned_contracts_weight(), 4 * 2 + 2 * (2 + 1));
+
+		let before = System::block_weight().total();
+		assert_eq!(submit(&ALICE, instantiate_with_code(DUMMY, ENDOWMENT, vec![])), Ok(Ok(())));
+		assert_eq!(System::block_weight().total(), before + ContractsGuard::owned_contracts_weight());
+
+		let before = System::block_weight().total();
+		let dummy = contract_address(&ALICE, DUMMY, &[]);
+		assert_eq!(submit(&BOB, call(&dummy, 0, vec![])), Ok(Ok(())));
+		assert_eq!(System::block_weight().total(), before);
+	});
+}
+
+#[test]
+fn salts_longer_than_max_salt_length_are_rejected() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(MaxSaltLength::get(), 4);
+		assert_eq!(
+			submit(&ALICE, instantiate_with_code(DUMMY, ENDOWMENT, vec![0; 5])),
+			Err(InvalidTransaction::Custom(SALT_TOO_LONG).into()),
+		);
+		assert_eq!(submit(&ALICE, instantiate_with_code(DUMMY, ENDOWMENT, vec![0; 4])), Ok(Ok(())));
+	});
+}
+
+#[test]
+fn call_values_are_rejected_unless_allowed() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(submit(&ALICE, instantiate_with_code(DUMMY, ENDOWMENT, vec![])), Ok(Ok(())));
+		let dest = contract_address(&ALICE, DUMMY, &[]);
+		assert_eq!(submit(&ALICE, call(&dest, 1, vec![])), Ok(Ok(())));
+
+		AllowCallValue::set(&false);
+		assert_eq!(
+			submit(&ALICE, call(&dest, 1, vec![])),
+			Err(InvalidTransaction::Custom(VALUE_TRANSFER_DISABLED).into()),
+		);
+		assert_eq!(submit(&ALICE, call(&dest, 0, vec![])), Ok(Ok(())));
+	});
+}
+
+#[test]
+fn endowments_below_min_endowment_are_rejected() {
+	new_test_ext().execute_with(|| {
+		let min = MinEndowment::get();
+		assert_eq!(
+			submit(&ALICE, instantiate_with_code(DUMMY, min - 1, vec![])),
+			Err(InvalidTransaction::Custom(ENDOWMENT_TOO_LOW).into()),
+		);
+		assert_eq!(submit(&ALICE, instantiate_with_code(DUMMY, min, vec![])), Ok(Ok(())));
+		assert_eq!(Balances::free_balance(contract_address(&ALICE, DUMMY, &[])), min);
+	});
+}
+
+#[test]
+fn only_whitelisted_code_is_instantiated_when_the_whitelist_is_enforced() {
+	new_test_ext().execute_with(|| {
+		EnforceCodeWhitelist::set(&true);
+		let not_whitelisted: TransactionValidityError =
+			InvalidTransaction::Custom(CODE_NOT_WHITELISTED).into();
+		assert_eq!(submit(&ALICE, instantiate_with_code(DUMMY, ENDOWMENT, vec![])), Err(not_whitelisted));
+
+		assert_ok!(ContractsGuard::whitelist_code(Origin::root(), code_hash(DUMMY)));
+		assert_eq!(submit(&ALICE, instantiate_with_code(DUMMY, ENDOWMENT, vec![])), Ok(Ok(())));
+		assert_eq!(
+			submit(&ALICE, instantiate_with_code(SELF_DESTRUCT, ENDOWMENT, vec![])),
+			Err(not_whitelisted),
+		);
+
+		assert_ok!(ContractsGuard::remove_whitelisted_code(Origin::root(), code_hash(DUMMY)));
+		assert_eq!(submit(&BOB, instantiate_with_code(DUMMY, ENDOWMENT, vec![])), Err(not_whitelisted));
+	});
+}
+
+#[test]
+fn instantiations_are_blocked_during_the_cooldown() {
+	new_test_ext().execute_with(|| {
+		InstantiationCooldown::set(&2);
+		assert_eq!(submit(&ALICE, instantiate_with_code(DUMMY, ENDOWMENT, vec![1])), Ok(Ok(())));
+
+		let cooling_down: TransactionValidityError =
+			InvalidTransaction::Custom(INSTANTIATION_COOLDOWN).into();
+		assert_eq!(submit(&ALICE, instantiate_with_code(DUMMY, ENDOWMENT, vec![2])), Err(cooling_down));
+		next_block();
+		assert_eq!(submit(&ALICE, instantiate_with_code(DUMMY, ENDOWMENT, vec![2])), Err(cooling_down));
+		// Other deployers are not affected.
+		assert_eq!(submit(&BOB, instantiate_with_code(DUMMY, ENDOWMENT, vec![2])), Ok(Ok(())));
+
+		next_block();
+		assert_eq!(submit(&ALICE, instantiate_with_code(DUMMY, ENDOWMENT, vec![2])), Ok(Ok(())));
+	});
+}
+
+#[test]
+fn inputs_longer_than_max_call_input_length_are_rejected() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(submit(&ALICE, instantiate_with_code(DUMMY, ENDOWMENT, vec![])), Ok(Ok(())));
+		let dest = contract_address(&ALICE, DUMMY, &[]);
+
+		assert_eq!(MaxCallInputLength::get(), 8);
+		assert_eq!(
+			submit(&ALICE, call(&dest, 0, vec![0; 9])),
+			Err(InvalidTransaction::Custom(INPUT_TOO_LONG).into()),
+		);
+		assert_eq!(submit(&ALICE, call(&dest, 0, vec![0; 8])), Ok(Ok(())));
+	});
+}
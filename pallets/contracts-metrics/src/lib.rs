@@ -0,0 +1,300 @@
+//! # Contracts Metrics Pallet
+//!
+//! Records how contracts are used so that explorers can query it without replaying
+//! the chain. The pallet does not observe the contracts pallet itself; the runtime
+//! reports the relevant dispatches to it (see `pallet_contracts_guard::OnContractCall`).
+//!
+//! ## Metrics
+//!
+//! * `CallCount`: The number of successful `call`s into each contract. Only the calls of
+//!   signed extrinsics are reported, the calls contracts make into other contracts are not.
+//! * `Provenance`: The deployer of each contract and the block it was instantiated in.
+//!   Only contracts instantiated by a signed extrinsic are recorded. The runtime rejects
+//!   contracts calls wrapped in other calls, so these are reported from the top-level
+//!   call; contracts instantiated by other contracts are not recorded.
+//! * `TotalStorage`: The number of contracts and the storage they use. The runtime reports
+//!   the storage of a contract whenever a signed extrinsic instantiated or called it, so
+//!   the totals are kept up to date without iterating over all contracts. Changes which
+//!   are not reported this way (those made by nested calls, contracts terminated by
+//!   other contracts and evictions) are picked up the next time the contract is called
+//!   directly. On every runtime upgrade the totals are recomputed from `AliveContracts`,
+//!   which also counts the contracts instantiated before this pallet was added.
+//! * `LastBlockWeight`: The weight consumed by the previous block, which the runtime
+//!   uses to scale the work it does for contracts in `on_initialize`.
+//! * `RecentEvents`: The events emitted by each contract in the last `EventRetention`
+//!   blocks. The runtime picks the contract events out of the events of the block via
+//!   `ContractEvents`. Older blocks are pruned, so the index only grows with the number
+//!   of events within the retention window. Decoding the events of a block takes time
+//!   linear in their number, so blocks with more than `MaxIndexedEvents` events are not
+//!   indexed. The work is added to the weight of the block once it is known.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use frame_support::{traits::Get, weights::Weight};
+use sp_runtime::{RuntimeDebug, traits::{One, Saturating, Zero}};
+use sp_std::prelude::*;
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+/// The storage used by the contracts in `ContractStorage`.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct StorageUsage {
+	/// The number of contracts.
+	pub contracts: u32,
+	/// The total size of the values in their storage in bytes.
+	pub bytes: u64,
+	/// The total number of items in their storage.
+	pub items: u64,
+}
+
+/// Extracts the events emitted by contracts from the events of the runtime.
+pub trait ContractEvents<AccountId, Event> {
+	/// Returns the address of the emitting contract and the event data, if `event`
+	/// was emitted by a contract.
+	fn contract_event(event: &Event) -> Option<(AccountId, Vec<u8>)>;
+}
+
+impl<AccountId, Event> ContractEvents<AccountId, Event> for () {
+	fn contract_event(_: &Event) -> Option<(AccountId, Vec<u8>)> {
+		None
+	}
+}
+
+/// Lists the contracts of the runtime together with the storage they use.
+pub trait AliveContracts<AccountId> {
+	/// Returns every alive contract and its storage as `(bytes, items)`.
+	fn alive_contracts() -> Vec<(AccountId, (u32, u32))>;
+}
+
+impl<AccountId> AliveContracts<AccountId> for () {
+	fn alive_contracts() -> Vec<(AccountId, (u32, u32))> {
+		Vec::new()
+	}
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::pallet_prelude::*;
+	use sp_std::prelude::*;
+	use super::{AliveContracts, ContractEvents, StorageUsage};
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Picks the contract events out of the events of a block.
+		type ContractEvents: ContractEvents<Self::AccountId, <Self as frame_system::Config>::Event>;
+
+		/// The number of blocks whose contract events are kept in `RecentEvents`.
+		/// Zero disables the index.
+		#[pallet::constant]
+		type EventRetention: Get<Self::BlockNumber>;
+
+		/// The maximum number of events (of any pallet) a block may have for its contract
+		/// events to be indexed.
+		#[pallet::constant]
+		type MaxIndexedEvents: Get<u32>;
+
+		/// Lists the contracts whose storage `TotalStorage` sums up.
+		type AliveContracts: AliveContracts<Self::AccountId>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(n: T::BlockNumber) -> Weight {
+			let pruned = Self::prune_events(n);
+			// Accounts for the write of `LastBlockWeight` in `on_finalize`. The events of the
+			// block are not known yet, `on_finalize` registers the weight of indexing them.
+			T::DbWeight::get().reads_writes(1 + pruned as Weight, 1 + pruned as Weight)
+		}
+
+		fn on_finalize(n: T::BlockNumber) {
+			let indexing = Self::index_events(n);
+			frame_system::Pallet::<T>::register_extra_weight_unchecked(
+				indexing,
+				DispatchClass::Mandatory,
+			);
+			LastBlockWeight::<T>::put(frame_system::Pallet::<T>::block_weight().total());
+		}
+
+		fn on_runtime_upgrade() -> Weight {
+			Self::reconcile_storage()
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {}
+
+	/// The number of successful calls into a contract.
+	#[pallet::storage]
+	#[pallet::getter(fn call_count)]
+	pub type CallCount<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, u64, ValueQuery>;
+
+	/// The deployer of a contract and the number of the block it was instantiated in.
+	#[pallet::storage]
+	#[pallet::getter(fn provenance)]
+	pub type Provenance<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		(T::AccountId, T::BlockNumber),
+	>;
+
+	/// The storage used by an alive contract when it was last reported, as `(bytes, items)`.
+	#[pallet::storage]
+	pub type ContractStorage<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, (u32, u32)>;
+
+	/// The sum of `ContractStorage`.
+	#[pallet::storage]
+	#[pallet::getter(fn total_storage)]
+	pub type TotalStorage<T> = StorageValue<_, StorageUsage, ValueQuery>;
+
+	/// The total weight consumed by the previous block.
+	#[pallet::storage]
+	#[pallet::getter(fn last_block_weight)]
+	pub type LastBlockWeight<T> = StorageValue<_, Weight, ValueQuery>;
+
+	/// The data of the events a contract emitted in a block, in the order of emission.
+	#[pallet::storage]
+	pub type RecentEvents<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		T::BlockNumber,
+		Blake2_128Concat,
+		T::AccountId,
+		Vec<Vec<u8>>,
+		ValueQuery,
+	>;
+}
+
+impl<T: Config> Pallet<T> {
+	/// Records a successful call into the contract at `address`.
+	pub fn note_call(address: &T::AccountId) {
+		CallCount::<T>::mutate(address, |count| *count = count.saturating_add(1));
+	}
+
+	/// Records that `deployer` instantiated the contract at `contract` in the current block.
+	pub fn note_instantiation(deployer: &T::AccountId, contract: &T::AccountId) {
+		let block = frame_system::Pallet::<T>::block_number();
+		Provenance::<T>::insert(contract, (deployer.clone(), block));
+	}
+
+	/// Records the storage of the contract at `address` as `(bytes, items)`, `None` if
+	/// it is no longer alive, and updates `TotalStorage` by the difference.
+	pub fn note_storage(address: &T::AccountId, usage: Option<(u32, u32)>) {
+		let previous = ContractStorage::<T>::get(address);
+		if previous == usage {
+			return;
+		}
+		TotalStorage::<T>::mutate(|total| {
+			if let Some((bytes, items)) = previous {
+				total.contracts = total.contracts.saturating_sub(1);
+				total.bytes = total.bytes.saturating_sub(bytes.into());
+				total.items = total.items.saturating_sub(items.into());
+			}
+			if let Some((bytes, items)) = usage {
+				total.contracts = total.contracts.saturating_add(1);
+				total.bytes = total.bytes.saturating_add(bytes.into());
+				total.items = total.items.saturating_add(items.into());
+			}
+		});
+		ContractStorage::<T>::set(address, usage);
+	}
+
+	/// Recomputes `ContractStorage` and `TotalStorage` from `AliveContracts`, dropping the
+	/// changes which were not reported. Returns the weight of doing so.
+	pub fn reconcile_storage() -> Weight {
+		let removed = ContractStorage::<T>::drain().count() as Weight;
+		let alive = T::AliveContracts::alive_contracts();
+		let mut total = StorageUsage::default();
+		for (address, (bytes, items)) in &alive {
+			total.contracts = total.contracts.saturating_add(1);
+			total.bytes = total.bytes.saturating_add((*bytes).into());
+			total.items = total.items.saturating_add((*items).into());
+			ContractStorage::<T>::insert(address, (*bytes, *items));
+		}
+		log::info!(
+			target: "runtime::contracts-metrics",
+			"Reconciled the storage of {} contracts: {} bytes in {} items",
+			total.contracts,
+			total.bytes,
+			total.items,
+		);
+		TotalStorage::<T>::put(total);
+		let alive = alive.len() as Weight;
+		T::DbWeight::get().reads_writes(removed + alive, removed + alive + 1)
+	}
+
+	/// Returns up to `count` events emitted by the contract at `address` within the
+	/// retention window together with the number of their block, newest first.
+	pub fn recent_events(address: &T::AccountId, count: u32) -> Vec<(T::BlockNumber, Vec<u8>)> {
+		let now = frame_system::Pallet::<T>::block_number();
+		let oldest = now.saturating_sub(T::EventRetention::get()).saturating_add(One::one());
+		let mut events = Vec::new();
+		let mut block = now;
+		while block >= oldest && !block.is_zero() && events.len() < count as usize {
+			let emitted = RecentEvents::<T>::get(block, address);
+			events.extend(
+				emitted.into_iter()
+					.rev()
+					.take(count as usize - events.len())
+					.map(|data| (block, data))
+			);
+			block -= One::one();
+		}
+		events
+	}
+
+	/// Indexes the contract events deposited in block `n` unless it has more than
+	/// `MaxIndexedEvents` events. Returns the weight of doing so.
+	fn index_events(n: T::BlockNumber) -> Weight {
+		if T::EventRetention::get().is_zero() {
+			return 0;
+		}
+		// `EventCount` is read without decoding the events.
+		let count = frame_system::Pallet::<T>::event_count();
+		if count > T::MaxIndexedEvents::get() {
+			log::debug!(
+				target: "runtime::contracts-metrics",
+				"Not indexing the {} events of block {:?}",
+				count,
+				n,
+			);
+			return T::DbWeight::get().reads(1);
+		}
+		let mut indexed: Weight = 0;
+		for record in frame_system::Pallet::<T>::events() {
+			if let Some((address, data)) = T::ContractEvents::contract_event(&record.event) {
+				RecentEvents::<T>::append(n, address, data);
+				indexed += 1;
+			}
+		}
+		T::DbWeight::get().reads_writes(2, indexed)
+	}
+
+	/// Removes the events of the block which leaves the retention window with block `n`.
+	/// Returns the number of removed entries.
+	fn prune_events(n: T::BlockNumber) -> u32 {
+		// With a zero retention nothing was indexed.
+		let retention = T::EventRetention::get();
+		if retention.is_zero() || n <= retention {
+			return 0;
+		}
+		let expired = n - retention;
+		let addresses: Vec<_> = RecentEvents::<T>::iter_prefix(expired)
+			.map(|(address, _)| address)
+			.collect();
+		for address in &addresses {
+			RecentEvents::<T>::remove(expired, address);
+		}
+		addresses.len() as u32
+	}
+}
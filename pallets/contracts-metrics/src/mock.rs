@@ -0,0 +1,101 @@
+//! Test utilities
+
+use crate as pallet_contracts_metrics;
+use frame_support::parameter_types;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+};
+use std::cell::RefCell;
+
+pub type AccountId = u64;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		ContractsMetrics: pallet_contracts_metrics::{Pallet, Storage},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub BlockWeights: frame_system::limits::BlockWeights =
+		frame_system::limits::BlockWeights::simple_max(1_000_000);
+}
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = ();
+	type BlockWeights = BlockWeights;
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Call = Call;
+	type Hashing = BlakeTwo256;
+	type AccountId = AccountId;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ();
+	type OnSetCode = ();
+}
+
+parameter_types! {
+	pub const EventRetention: u64 = 3;
+	pub const MaxIndexedEvents: u32 = 10;
+}
+
+thread_local! {
+	static CONTRACTS: RefCell<Vec<(AccountId, (u32, u32))>> = RefCell::new(Vec::new());
+}
+
+/// The contracts of the test runtime, which the tests set directly.
+pub struct Contracts;
+
+impl Contracts {
+	pub fn set(contracts: Vec<(AccountId, (u32, u32))>) {
+		CONTRACTS.with(|c| *c.borrow_mut() = contracts);
+	}
+}
+
+impl crate::AliveContracts<AccountId> for Contracts {
+	fn alive_contracts() -> Vec<(AccountId, (u32, u32))> {
+		CONTRACTS.with(|c| c.borrow().clone())
+	}
+}
+
+impl pallet_contracts_metrics::Config for Test {
+	type ContractEvents = ();
+	type EventRetention = EventRetention;
+	type MaxIndexedEvents = MaxIndexedEvents;
+	type AliveContracts = Contracts;
+}
+
+pub const ALICE: AccountId = 1;
+pub const BOB: AccountId = 2;
+pub const CONTRACT: AccountId = 10;
+pub const OTHER_CONTRACT: AccountId = 11;
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
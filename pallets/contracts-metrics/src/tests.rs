@@ -0,0 +1,105 @@
+//! Tests for the contracts metrics pallet.
+
+use super::*;
+use crate::mock::*;
+use frame_support::traits::{OnInitialize, OnRuntimeUpgrade};
+
+fn usage(contracts: u32, bytes: u64, items: u64) -> StorageUsage {
+	StorageUsage { contracts, bytes, items }
+}
+
+#[test]
+fn calls_are_counted_per_contract() {
+	new_test_ext().execute_with(|| {
+		ContractsMetrics::note_call(&CONTRACT);
+		ContractsMetrics::note_call(&CONTRACT);
+		ContractsMetrics::note_call(&OTHER_CONTRACT);
+
+		assert_eq!(ContractsMetrics::call_count(CONTRACT), 2);
+		assert_eq!(ContractsMetrics::call_count(OTHER_CONTRACT), 1);
+		assert_eq!(ContractsMetrics::call_count(ALICE), 0);
+	});
+}
+
+#[test]
+fn instantiations_record_the_deployer_and_block() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(5);
+		ContractsMetrics::note_instantiation(&ALICE, &CONTRACT);
+
+		assert_eq!(ContractsMetrics::provenance(CONTRACT), Some((ALICE, 5)));
+		assert_eq!(ContractsMetrics::provenance(OTHER_CONTRACT), None);
+	});
+}
+
+#[test]
+fn reported_storage_updates_the_totals() {
+	new_test_ext().execute_with(|| {
+		ContractsMetrics::note_storage(&CONTRACT, Some((10, 1)));
+		ContractsMetrics::note_storage(&OTHER_CONTRACT, Some((20, 2)));
+		assert_eq!(ContractsMetrics::total_storage(), usage(2, 30, 3));
+
+		ContractsMetrics::note_storage(&CONTRACT, Some((15, 2)));
+		assert_eq!(ContractsMetrics::total_storage(), usage(2, 35, 4));
+
+		ContractsMetrics::note_storage(&OTHER_CONTRACT, None);
+		assert_eq!(ContractsMetrics::total_storage(), usage(1, 15, 2));
+		// A contract which is already gone is not subtracted twice.
+		ContractsMetrics::note_storage(&OTHER_CONTRACT, None);
+		assert_eq!(ContractsMetrics::total_storage(), usage(1, 15, 2));
+	});
+}
+
+#[test]
+fn runtime_upgrades_count_the_contracts_instantiated_before() {
+	new_test_ext().execute_with(|| {
+		Contracts::set(vec![(CONTRACT, (10, 1)), (OTHER_CONTRACT, (20, 2))]);
+		assert_eq!(ContractsMetrics::total_storage(), usage(0, 0, 0));
+
+		ContractsMetrics::on_runtime_upgrade();
+		assert_eq!(ContractsMetrics::total_storage(), usage(2, 30, 3));
+
+		// Reports after the upgrade apply to the reconciled storage.
+		ContractsMetrics::note_storage(&CONTRACT, None);
+		assert_eq!(ContractsMetrics::total_storage(), usage(1, 20, 2));
+	});
+}
+
+#[test]
+fn runtime_upgrades_drop_the_changes_which_were_not_reported() {
+	new_test_ext().execute_with(|| {
+		ContractsMetrics::note_storage(&CONTRACT, Some((10, 1)));
+		ContractsMetrics::note_storage(&OTHER_CONTRACT, Some((20, 2)));
+		// A nested call grew `CONTRACT` and terminated `OTHER_CONTRACT`.
+		Contracts::set(vec![(CONTRACT, (50, 5))]);
+
+		ContractsMetrics::on_runtime_upgrade();
+		assert_eq!(ContractsMetrics::total_storage(), usage(1, 50, 5));
+		assert_eq!(ContractStorage::<Test>::get(CONTRACT), Some((50, 5)));
+		assert_eq!(ContractStorage::<Test>::get(OTHER_CONTRACT), None);
+	});
+}
+
+#[test]
+fn recent_events_are_returned_newest_first_within_the_retention() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(EventRetention::get(), 3);
+		for block in 1..=4 {
+			RecentEvents::<Test>::append(block, CONTRACT, vec![block as u8]);
+		}
+		RecentEvents::<Test>::append(4, CONTRACT, vec![40]);
+		RecentEvents::<Test>::append(4, OTHER_CONTRACT, vec![41]);
+
+		System::set_block_number(4);
+		ContractsMetrics::on_initialize(4);
+		assert!(!RecentEvents::<Test>::contains_key(1, CONTRACT));
+
+		assert_eq!(
+			ContractsMetrics::recent_events(&CONTRACT, 10),
+			vec![(4, vec![40]), (4, vec![4]), (3, vec![3]), (2, vec![2])],
+		);
+		assert_eq!(ContractsMetrics::recent_events(&CONTRACT, 2), vec![(4, vec![40]), (4, vec![4])]);
+		assert_eq!(ContractsMetrics::recent_events(&OTHER_CONTRACT, 10), vec![(4, vec![41])]);
+		assert_eq!(ContractsMetrics::recent_events(&BOB, 10), vec![]);
+	});
+}
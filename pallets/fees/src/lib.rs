@@ -0,0 +1,431 @@
+//! # Fees Pallet
+//!
+//! Canvas specific fee policies on top of `pallet_transaction_payment`.
+//!
+//! The policies are applied by [`ChargeTransactionPayment`], a drop-in replacement for
+//! the signed extension of the same name in `pallet_transaction_payment`. It has the same
+//! encoding and identifier, so clients do not need to be aware of it.
+//!
+//! ## Policies
+//!
+//! * `MinimumPriorityTip`: Tips below this amount are still paid, but they do not
+//!   increase the priority of a transaction. This prevents sub-unit tips from
+//!   reordering the transaction pool.
+//! * Fee exemptions: Accounts which are exempted by root (e.g. a faucet or a relayer) do
+//!   not pay any fees. Their transactions still consume block weight as usual.
+//! * Staking: Accounts which lock at least `MinimumStake` via [`Pallet::stake`] get the
+//!   priority of their `BoostedCalls` (the contract calls in Canvas) multiplied by
+//!   `StakePriorityMultiplier`. The stake is a `pallet_balances` lock, so it can still be
+//!   used to pay fees but not be transferred. After [`Pallet::unstake`] the boost ends right
+//!   away, but the stake stays locked for `UnstakeDelay` blocks before it can be withdrawn
+//!   with [`Pallet::withdraw_unstaked`]. This makes the boost a long-term commitment
+//!   instead of something to lock just for a single transaction.
+//! * Refunds: A dispatch which fails with one of the `RefundedErrors` (the reverted
+//!   contract calls in Canvas) only pays `1 - FailureRefund` of its fee. The tip is
+//!   always paid in full. A zero `FailureRefund` disables the refunds.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use frame_support::{
+	storage::{with_transaction, TransactionOutcome},
+	traits::{Filter, LockIdentifier, WithdrawReasons},
+	weights::{DispatchInfo, PostDispatchInfo},
+};
+use pallet_transaction_payment::OnChargeTransaction;
+use sp_runtime::{
+	traits::{
+		DispatchInfoOf, Dispatchable, PostDispatchInfoOf, Saturating, SignedExtension, Zero,
+	},
+	transaction_validity::{TransactionValidity, TransactionValidityError, ValidTransaction},
+	DispatchError, DispatchResult, FixedPointOperand,
+};
+use sp_std::fmt;
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+type BalanceOf<T> = <<T as pallet_transaction_payment::Config>::OnChargeTransaction
+	as OnChargeTransaction<T>>::Balance;
+
+/// Decides which failed dispatches get part of their fee refunded.
+pub trait RefundedErrors {
+	/// Whether a dispatch which failed with `error` gets `FailureRefund` of its fee refunded.
+	fn is_refunded(error: &DispatchError) -> bool;
+}
+
+impl RefundedErrors for () {
+	fn is_refunded(_: &DispatchError) -> bool {
+		false
+	}
+}
+
+/// The identifier of the lock which holds the stake of an account.
+const STAKE_ID: LockIdentifier = *b"feestake";
+
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::{
+		pallet_prelude::*,
+		traits::{Currency, Filter, LockableCurrency},
+	};
+	use frame_system::pallet_prelude::*;
+	use sp_runtime::Perbill;
+	use super::{BalanceOf, RefundedErrors};
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config + pallet_transaction_payment::Config {
+		/// The overarching event type.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// Tips below this amount do not increase the priority of a transaction.
+		#[pallet::constant]
+		type MinimumPriorityTip: Get<BalanceOf<Self>>;
+
+		/// The currency in which stakes are locked.
+		type Currency: LockableCurrency<Self::AccountId, Balance = BalanceOf<Self>>;
+
+		/// The calls whose priority is boosted for staked accounts.
+		type BoostedCalls: Filter<<Self as frame_system::Config>::Call>;
+
+		/// The minimum stake an account needs to lock to get boosted priority.
+		#[pallet::constant]
+		type MinimumStake: Get<BalanceOf<Self>>;
+
+		/// The factor by which the priority of boosted calls is multiplied.
+		#[pallet::constant]
+		type StakePriorityMultiplier: Get<u64>;
+
+		/// The number of blocks an unstaked amount stays locked before it can be withdrawn.
+		#[pallet::constant]
+		type UnstakeDelay: Get<Self::BlockNumber>;
+
+		/// The errors for which part of the fee of a failed dispatch is refunded.
+		type RefundedErrors: RefundedErrors;
+
+		/// The fraction of the fee (without the tip) which is refunded for `RefundedErrors`.
+		#[pallet::constant]
+		type FailureRefund: Get<Perbill>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Exempt `who` from paying transaction fees.
+		///
+		/// The dispatch origin for this call must be _Root_.
+		#[pallet::weight(T::DbWeight::get().writes(1))]
+		pub fn add_fee_exemption(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+			ensure_root(origin)?;
+			FeeExempt::<T>::insert(&who, ());
+			Self::deposit_event(Event::FeeExemptionAdded(who));
+			Ok(())
+		}
+
+		/// Make `who` pay transaction fees again.
+		///
+		/// The dispatch origin for this call must be _Root_.
+		#[pallet::weight(T::DbWeight::get().writes(1))]
+		pub fn remove_fee_exemption(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+			ensure_root(origin)?;
+			FeeExempt::<T>::remove(&who);
+			Self::deposit_event(Event::FeeExemptionRemoved(who));
+			Ok(())
+		}
+
+		/// Lock `amount` of the free balance of the origin as stake.
+		///
+		/// Replaces any previous stake of the origin. The stake needs to be at least
+		/// `MinimumStake`. If the previous stake was higher, the difference is unstaked.
+		#[pallet::weight(T::DbWeight::get().reads_writes(3, 3))]
+		pub fn stake(origin: OriginFor<T>, #[pallet::compact] amount: BalanceOf<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(amount >= T::MinimumStake::get(), Error::<T>::StakeTooLow);
+			ensure!(T::Currency::free_balance(&who) >= amount, Error::<T>::InsufficientBalance);
+			if let Some(previous) = Stakes::<T>::get(&who) {
+				if previous > amount {
+					Self::note_unstaked(&who, previous);
+				}
+			}
+			Stakes::<T>::insert(&who, amount);
+			Self::update_lock(&who);
+			Self::deposit_event(Event::Staked(who, amount));
+			Ok(())
+		}
+
+		/// Unstake the stake of the origin.
+		///
+		/// The stake no longer boosts the priority of the origin, but it stays locked until
+		/// it is withdrawn with `withdraw_unstaked` after `UnstakeDelay` blocks.
+		#[pallet::weight(T::DbWeight::get().reads_writes(3, 3))]
+		pub fn unstake(origin: OriginFor<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let amount = Stakes::<T>::take(&who).ok_or(Error::<T>::NotStaked)?;
+			Self::note_unstaked(&who, amount);
+			Self::deposit_event(Event::Unstaked(who, amount));
+			Ok(())
+		}
+
+		/// Unlock the unstaked amount of the origin once its `UnstakeDelay` passed.
+		#[pallet::weight(T::DbWeight::get().reads_writes(3, 2))]
+		pub fn withdraw_unstaked(origin: OriginFor<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let (amount, unlock_at) = Unstaking::<T>::get(&who).ok_or(Error::<T>::NotUnstaking)?;
+			ensure!(
+				frame_system::Pallet::<T>::block_number() >= unlock_at,
+				Error::<T>::StillLocked,
+			);
+			Unstaking::<T>::remove(&who);
+			Self::update_lock(&who);
+			Self::deposit_event(Event::Withdrawn(who, amount));
+			Ok(())
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::metadata(T::AccountId = "AccountId")]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// An account was exempted from transaction fees. \[who\]
+		FeeExemptionAdded(T::AccountId),
+		/// An account has to pay transaction fees again. \[who\]
+		FeeExemptionRemoved(T::AccountId),
+		/// An account locked a stake. \[who, amount\]
+		Staked(T::AccountId, BalanceOf<T>),
+		/// An account unstaked, its stake stays locked for `UnstakeDelay`. \[who, amount\]
+		Unstaked(T::AccountId, BalanceOf<T>),
+		/// An account withdrew its unstaked amount. \[who, amount\]
+		Withdrawn(T::AccountId, BalanceOf<T>),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The stake is below `MinimumStake`.
+		StakeTooLow,
+		/// The free balance is lower than the stake.
+		InsufficientBalance,
+		/// The account has no stake to unstake.
+		NotStaked,
+		/// The account has no unstaked amount to withdraw.
+		NotUnstaking,
+		/// The `UnstakeDelay` of the unstaked amount has not passed yet.
+		StillLocked,
+	}
+
+	/// Accounts which do not pay transaction fees.
+	#[pallet::storage]
+	pub type FeeExempt<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, ()>;
+
+	/// The stake locked by an account.
+	#[pallet::storage]
+	#[pallet::getter(fn stake_of)]
+	pub type Stakes<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, BalanceOf<T>>;
+
+	/// The amount an account unstaked and the block from which on it can be withdrawn.
+	#[pallet::storage]
+	#[pallet::getter(fn unstaking_of)]
+	pub type Unstaking<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		(BalanceOf<T>, T::BlockNumber),
+	>;
+}
+
+impl<T: Config> Pallet<T> {
+	/// Whether `who` is exempted from paying transaction fees.
+	pub fn is_fee_exempt(who: &T::AccountId) -> bool {
+		FeeExempt::<T>::contains_key(who)
+	}
+
+	/// Whether `who` locked enough stake to get boosted priority.
+	///
+	/// Fees can be paid from the stake, so only the part of it which is still there counts.
+	pub fn is_staked(who: &T::AccountId) -> bool {
+		Stakes::<T>::get(who).map_or(false, |stake| {
+			stake.min(T::Currency::free_balance(who)) >= T::MinimumStake::get()
+		})
+	}
+
+	/// Starts the `UnstakeDelay` of `amount`, together with any amount still unstaking.
+	fn note_unstaked(who: &T::AccountId, amount: BalanceOf<T>) {
+		let unlock_at = frame_system::Pallet::<T>::block_number()
+			.saturating_add(T::UnstakeDelay::get());
+		Unstaking::<T>::mutate(who, |unstaking| {
+			let pending = unstaking.map_or_else(Zero::zero, |(pending, _)| pending);
+			*unstaking = Some((pending.max(amount), unlock_at));
+		});
+	}
+
+	/// Locks the stake and the unstaked amount of `who`, whichever is higher.
+	fn update_lock(who: &T::AccountId) {
+		let staked = Stakes::<T>::get(who).unwrap_or_else(Zero::zero);
+		let unstaking = Unstaking::<T>::get(who).map_or_else(Zero::zero, |(amount, _)| amount);
+		let locked = staked.max(unstaking);
+		if locked.is_zero() {
+			T::Currency::remove_lock(STAKE_ID, who);
+		} else {
+			// Locked funds can still pay for transactions.
+			let reasons = WithdrawReasons::except(WithdrawReasons::TRANSACTION_PAYMENT);
+			T::Currency::set_lock(STAKE_ID, who, locked, reasons);
+		}
+	}
+}
+
+/// Require the transactor pay for themselves and maybe include a tip to gain additional
+/// priority in the queue.
+///
+/// Wraps `pallet_transaction_payment::ChargeTransactionPayment` and applies the
+/// policies of this pallet.
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct ChargeTransactionPayment<T: Config>(#[codec(compact)] BalanceOf<T>);
+
+impl<T: Config> ChargeTransactionPayment<T> {
+	/// Utility constructor. Used only in client/factory code.
+	pub fn from(tip: BalanceOf<T>) -> Self {
+		Self(tip)
+	}
+
+	fn inner(&self) -> pallet_transaction_payment::ChargeTransactionPayment<T> {
+		pallet_transaction_payment::ChargeTransactionPayment::from(self.0)
+	}
+
+	/// Validates the payment of the transaction according to the fee exemption and the
+	/// minimum priority tip.
+	fn validate_payment(
+		&self,
+		who: &T::AccountId,
+		call: &T::Call,
+		info: &DispatchInfoOf<T::Call>,
+		len: usize,
+	) -> TransactionValidity where
+		BalanceOf<T>: Send + Sync + From<u64> + FixedPointOperand,
+		T::Call: Dispatchable<Info=DispatchInfo, PostInfo=PostDispatchInfo>,
+	{
+		if Pallet::<T>::is_fee_exempt(who) {
+			// Nothing is withdrawn, but the transaction gets the priority an untipped
+			// transaction of the same size and weight would get.
+			let priority = with_transaction(|| TransactionOutcome::Rollback(
+				pallet_transaction_payment::ChargeTransactionPayment::<T>::from(Zero::zero())
+					.validate(who, call, info, len)
+			)).map(|valid| valid.priority).unwrap_or_default();
+			return Ok(ValidTransaction {
+				priority,
+				..Default::default()
+			});
+		}
+
+		if self.0.is_zero() || self.0 >= T::MinimumPriorityTip::get() {
+			return self.inner().validate(who, call, info, len);
+		}
+
+		// The tip is too small to be taken into account, so the transaction gets the
+		// priority it would have without any tip. The fee withdrawal of this dry-run
+		// must not influence the actual validation below.
+		let untipped = with_transaction(|| TransactionOutcome::Rollback(
+			pallet_transaction_payment::ChargeTransactionPayment::<T>::from(Zero::zero())
+				.validate(who, call, info, len)
+		))?;
+
+		let tipped = self.inner().validate(who, call, info, len)?;
+		Ok(ValidTransaction {
+			priority: untipped.priority,
+			..tipped
+		})
+	}
+}
+
+impl<T: Config> fmt::Debug for ChargeTransactionPayment<T> {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "ChargeTransactionPayment<{:?}>", self.0)
+	}
+
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut fmt::Formatter) -> fmt::Result {
+		Ok(())
+	}
+}
+
+impl<T: Config> SignedExtension for ChargeTransactionPayment<T> where
+	BalanceOf<T>: Send + Sync + From<u64> + FixedPointOperand,
+	T::Call: Dispatchable<Info=DispatchInfo, PostInfo=PostDispatchInfo>,
+{
+	const IDENTIFIER: &'static str = "ChargeTransactionPayment";
+	type AccountId = T::AccountId;
+	type Call = T::Call;
+	type AdditionalSigned = ();
+	type Pre = <pallet_transaction_payment::ChargeTransactionPayment<T> as SignedExtension>::Pre;
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+
+	fn validate(
+		&self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> TransactionValidity {
+		let mut valid = self.validate_payment(who, call, info, len)?;
+		if T::BoostedCalls::filter(call) && Pallet::<T>::is_staked(who) {
+			valid.priority = valid.priority.saturating_mul(T::StakePriorityMultiplier::get());
+		}
+		Ok(valid)
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> Result<Self::Pre, TransactionValidityError> {
+		if Pallet::<T>::is_fee_exempt(who) {
+			// Without any withdrawn liquidity `post_dispatch` neither refunds nor
+			// deposits a fee.
+			return Ok((Zero::zero(), who.clone(), Default::default()));
+		}
+		self.inner().pre_dispatch(who, call, info, len)
+	}
+
+	fn post_dispatch(
+		pre: Self::Pre,
+		info: &DispatchInfoOf<Self::Call>,
+		post_info: &PostDispatchInfoOf<Self::Call>,
+		len: usize,
+		result: &DispatchResult,
+	) -> Result<(), TransactionValidityError> {
+		let refund = T::FailureRefund::get();
+		let refunded = match result {
+			Err(error) => !refund.is_zero() && T::RefundedErrors::is_refunded(error),
+			Ok(()) => false,
+		};
+		if !refunded {
+			return pallet_transaction_payment::ChargeTransactionPayment::<T>::post_dispatch(
+				pre, info, post_info, len, result,
+			);
+		}
+
+		// Charges the fee like `pallet_transaction_payment` does, less the refund.
+		let (tip, who, imbalance) = pre;
+		let actual_fee = pallet_transaction_payment::Module::<T>::compute_actual_fee(
+			len as u32, info, post_info, tip,
+		);
+		let fee = actual_fee.saturating_sub(refund * actual_fee.saturating_sub(tip));
+		T::OnChargeTransaction::correct_and_deposit_fee(
+			&who, info, post_info, fee, tip, imbalance,
+		)
+	}
+}
@@ -0,0 +1,164 @@
+//! Test utilities
+
+use crate as pallet_fees;
+use frame_support::{
+	parameter_types,
+	traits::{Filter, GenesisBuild},
+	weights::{DispatchInfo, IdentityFee, Weight},
+};
+use pallet_transaction_payment::CurrencyAdapter;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+	DispatchError, Perbill,
+};
+
+pub type AccountId = u64;
+pub type Balance = u64;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+		TransactionPayment: pallet_transaction_payment::{Pallet, Storage},
+		Fees: pallet_fees::{Pallet, Call, Storage, Event<T>},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub BlockWeights: frame_system::limits::BlockWeights =
+		frame_system::limits::BlockWeights::simple_max(1_000_000);
+}
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = ();
+	type BlockWeights = BlockWeights;
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Call = Call;
+	type Hashing = BlakeTwo256;
+	type AccountId = AccountId;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<Balance>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ();
+	type OnSetCode = ();
+}
+
+parameter_types! {
+	pub const ExistentialDeposit: Balance = 1;
+	pub const MaxLocks: u32 = 50;
+}
+
+impl pallet_balances::Config for Test {
+	type MaxLocks = MaxLocks;
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+	type Balance = Balance;
+	type Event = Event;
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const TransactionByteFee: Balance = 1;
+}
+
+impl pallet_transaction_payment::Config for Test {
+	type OnChargeTransaction = CurrencyAdapter<Balances, ()>;
+	type TransactionByteFee = TransactionByteFee;
+	type WeightToFee = IdentityFee<Balance>;
+	type FeeMultiplierUpdate = ();
+}
+
+parameter_types! {
+	pub storage MinimumPriorityTip: Balance = 100;
+	pub storage MinimumStake: Balance = 1_000;
+	pub storage StakePriorityMultiplier: u64 = 2;
+	pub storage UnstakeDelay: u64 = 10;
+	pub storage FailureRefund: Perbill = Perbill::zero();
+}
+
+/// Balance transfers take the role of the contract calls of the runtime.
+pub struct Transfers;
+
+impl Filter<Call> for Transfers {
+	fn filter(call: &Call) -> bool {
+		matches!(call, Call::Balances(_))
+	}
+}
+
+/// Transfers which failed because the sender lacked the funds are refunded.
+pub struct InsufficientBalance;
+
+impl crate::RefundedErrors for InsufficientBalance {
+	fn is_refunded(error: &DispatchError) -> bool {
+		*error == pallet_balances::Error::<Test>::InsufficientBalance.into()
+	}
+}
+
+impl pallet_fees::Config for Test {
+	type Event = Event;
+	type MinimumPriorityTip = MinimumPriorityTip;
+	type Currency = Balances;
+	type BoostedCalls = Transfers;
+	type MinimumStake = MinimumStake;
+	type StakePriorityMultiplier = StakePriorityMultiplier;
+	type UnstakeDelay = UnstakeDelay;
+	type RefundedErrors = InsufficientBalance;
+	type FailureRefund = FailureRefund;
+}
+
+pub const ALICE: AccountId = 1;
+pub const BOB: AccountId = 2;
+pub const CHARLIE: AccountId = 3;
+
+/// The length of the extrinsics used in the tests.
+pub const LEN: usize = 10;
+
+/// The dispatch info of the calls used in the tests.
+pub fn info() -> DispatchInfo {
+	DispatchInfo { weight: 1_000 as Weight, ..Default::default() }
+}
+
+pub fn remark() -> Call {
+	Call::System(frame_system::Call::remark(vec![]))
+}
+
+pub fn transfer(dest: AccountId, value: Balance) -> Call {
+	Call::Balances(pallet_balances::Call::transfer(dest, value))
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(ALICE, 1_000_000), (BOB, 1_000_000), (CHARLIE, 1_000_000)],
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
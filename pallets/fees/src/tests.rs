@@ -0,0 +1,221 @@
+//! Tests for the fees pallet.
+
+use super::*;
+use crate::mock::*;
+use frame_support::{assert_noop, assert_ok};
+use sp_runtime::{traits::BadOrigin, transaction_validity::TransactionPriority, Perbill};
+
+fn priority(who: AccountId, tip: Balance, call: Call) -> TransactionPriority {
+	ChargeTransactionPayment::<Test>::from(tip)
+		.validate(&who, &call, &info(), LEN)
+		.unwrap()
+		.priority
+}
+
+/// The fee of the calls used in the tests without any tip: the length and the weight.
+const FEE: Balance = LEN as Balance + 1_000;
+
+#[test]
+fn tips_below_the_minimum_do_not_increase_the_priority() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(MinimumPriorityTip::get(), 100);
+		let untipped = priority(ALICE, 0, remark());
+
+		assert_eq!(priority(ALICE, 1, remark()), untipped);
+		assert_eq!(priority(ALICE, 99, remark()), untipped);
+		assert!(priority(ALICE, 100, remark()) > untipped);
+		assert!(priority(ALICE, 200, remark()) > priority(ALICE, 100, remark()));
+	});
+}
+
+#[test]
+fn tips_below_the_minimum_are_still_paid() {
+	new_test_ext().execute_with(|| {
+		let pre = ChargeTransactionPayment::<Test>::from(99)
+			.pre_dispatch(&ALICE, &remark(), &info(), LEN)
+			.unwrap();
+		assert_eq!(Balances::free_balance(ALICE), 1_000_000 - FEE - 99);
+
+		assert_ok!(ChargeTransactionPayment::<Test>::post_dispatch(
+			pre,
+			&info(),
+			&Default::default(),
+			LEN,
+			&Ok(()),
+		));
+		assert_eq!(Balances::free_balance(ALICE), 1_000_000 - FEE - 99);
+	});
+}
+
+#[test]
+fn validating_a_small_tip_does_not_withdraw_the_fee_twice() {
+	new_test_ext().execute_with(|| {
+		let valid = ChargeTransactionPayment::<Test>::from(99)
+			.validate(&ALICE, &remark(), &info(), LEN);
+		assert!(valid.is_ok());
+		// The dry-run without the tip is rolled back, only the tipped validation withdraws.
+		assert_eq!(Balances::free_balance(ALICE), 1_000_000 - FEE - 99);
+	});
+}
+
+/// Runs the fee payment of `call` signed by `who` and returns what it cost.
+fn pay(who: AccountId, tip: Balance, call: Call) -> Balance {
+	pay_with_result(who, tip, call, &Ok(()))
+}
+
+/// Like `pay`, but for a dispatch of `call` which ended with `result`.
+fn pay_with_result(who: AccountId, tip: Balance, call: Call, result: &DispatchResult) -> Balance {
+	let before = Balances::free_balance(who);
+	let pre = ChargeTransactionPayment::<Test>::from(tip)
+		.pre_dispatch(&who, &call, &info(), LEN)
+		.unwrap();
+	assert_ok!(ChargeTransactionPayment::<Test>::post_dispatch(
+		pre,
+		&info(),
+		&Default::default(),
+		LEN,
+		result,
+	));
+	before - Balances::free_balance(who)
+}
+
+#[test]
+fn exempted_accounts_pay_no_fees() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Fees::add_fee_exemption(Origin::root(), ALICE));
+
+		assert_eq!(pay(ALICE, 0, transfer(CHARLIE, 10)), 0);
+		assert_eq!(pay(BOB, 0, transfer(CHARLIE, 10)), FEE);
+	});
+}
+
+#[test]
+fn exempted_accounts_get_the_priority_of_untipped_transactions() {
+	new_test_ext().execute_with(|| {
+		let untipped = priority(BOB, 0, remark());
+		assert_ok!(Fees::add_fee_exemption(Origin::root(), ALICE));
+
+		assert!(untipped > 0);
+		assert_eq!(priority(ALICE, 0, remark()), untipped);
+	});
+}
+
+#[test]
+fn fee_exemptions_can_be_removed() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(Fees::add_fee_exemption(Origin::signed(ALICE), ALICE), BadOrigin);
+		assert_ok!(Fees::add_fee_exemption(Origin::root(), ALICE));
+		assert_ok!(Fees::remove_fee_exemption(Origin::root(), ALICE));
+
+		assert_eq!(pay(ALICE, 0, remark()), FEE);
+	});
+}
+
+#[test]
+fn staked_accounts_get_boosted_priority_for_boosted_calls() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Fees::stake(Origin::signed(ALICE), MinimumStake::get() - 1),
+			Error::<Test>::StakeTooLow,
+		);
+		assert_ok!(Fees::stake(Origin::signed(ALICE), MinimumStake::get()));
+
+		let tip = MinimumPriorityTip::get();
+		let staked = priority(ALICE, tip, transfer(CHARLIE, 1));
+		let unstaked = priority(BOB, tip, transfer(CHARLIE, 1));
+		assert!(unstaked > 0);
+		assert_eq!(staked, unstaked * StakePriorityMultiplier::get());
+		assert_eq!(priority(ALICE, tip, remark()), priority(BOB, tip, remark()));
+	});
+}
+
+#[test]
+fn the_stake_can_pay_fees_but_not_be_transferred() {
+	new_test_ext().execute_with(|| {
+		let balance = Balances::free_balance(ALICE);
+		assert_ok!(Fees::stake(Origin::signed(ALICE), balance));
+
+		assert!(Balances::transfer(Origin::signed(ALICE), BOB, 1).is_err());
+		assert_eq!(pay(ALICE, 0, remark()), FEE);
+		assert_eq!(Balances::free_balance(ALICE), balance - FEE);
+	});
+}
+
+#[test]
+fn unstaked_funds_stay_locked_for_the_unstake_delay() {
+	new_test_ext().execute_with(|| {
+		let stake = MinimumStake::get();
+		assert_ok!(Fees::stake(Origin::signed(ALICE), stake));
+		assert_ok!(Fees::unstake(Origin::signed(ALICE)));
+
+		// The boost ends right away, the lock does not.
+		assert!(!Fees::is_staked(&ALICE));
+		assert_eq!(Fees::unstaking_of(ALICE), Some((stake, 1 + UnstakeDelay::get())));
+		let balance = Balances::free_balance(ALICE);
+		assert!(Balances::transfer(Origin::signed(ALICE), BOB, balance - stake + 1).is_err());
+		assert_noop!(Fees::withdraw_unstaked(Origin::signed(ALICE)), Error::<Test>::StillLocked);
+
+		System::set_block_number(1 + UnstakeDelay::get());
+		assert_ok!(Fees::withdraw_unstaked(Origin::signed(ALICE)));
+		assert_eq!(Fees::unstaking_of(ALICE), None);
+		assert_ok!(Balances::transfer(Origin::signed(ALICE), BOB, balance));
+		assert_noop!(Fees::withdraw_unstaked(Origin::signed(ALICE)), Error::<Test>::NotUnstaking);
+	});
+}
+
+#[test]
+fn lowering_the_stake_unstakes_the_difference() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Fees::stake(Origin::signed(ALICE), 3 * MinimumStake::get()));
+		assert_ok!(Fees::stake(Origin::signed(ALICE), MinimumStake::get()));
+
+		assert!(Fees::is_staked(&ALICE));
+		assert_eq!(
+			Fees::unstaking_of(ALICE),
+			Some((3 * MinimumStake::get(), 1 + UnstakeDelay::get())),
+		);
+		let balance = Balances::free_balance(ALICE);
+		let unlocked = balance - MinimumStake::get();
+		assert!(Balances::transfer(Origin::signed(ALICE), BOB, unlocked).is_err());
+
+		// Once withdrawn only the new stake stays locked.
+		System::set_block_number(1 + UnstakeDelay::get());
+		assert_ok!(Fees::withdraw_unstaked(Origin::signed(ALICE)));
+		assert_ok!(Balances::transfer(Origin::signed(ALICE), BOB, unlocked));
+		assert!(Balances::transfer(Origin::signed(ALICE), BOB, 1).is_err());
+	});
+}
+
+#[test]
+fn fees_paid_from_the_stake_end_the_boost() {
+	new_test_ext().execute_with(|| {
+		let stake = MinimumStake::get();
+		let balance = Balances::free_balance(ALICE);
+		assert_ok!(Balances::transfer(Origin::signed(ALICE), BOB, balance - stake - FEE / 2));
+		assert_ok!(Fees::stake(Origin::signed(ALICE), stake));
+		assert!(Fees::is_staked(&ALICE));
+
+		assert_eq!(pay(ALICE, 0, remark()), FEE);
+		assert_eq!(Fees::stake_of(ALICE), Some(stake));
+		assert!(!Fees::is_staked(&ALICE));
+	});
+}
+
+#[test]
+fn refunded_errors_get_the_failure_refund_of_the_fee_back() {
+	new_test_ext().execute_with(|| {
+		let refunded: DispatchResult = Err(pallet_balances::Error::<Test>::InsufficientBalance.into());
+		let not_refunded: DispatchResult = Err(pallet_balances::Error::<Test>::KeepAlive.into());
+		let tip = MinimumPriorityTip::get();
+
+		// Refunds are disabled by default.
+		assert_eq!(pay_with_result(ALICE, 0, transfer(BOB, 1), &refunded), FEE);
+
+		FailureRefund::set(&Perbill::from_percent(50));
+		assert_eq!(pay_with_result(ALICE, 0, transfer(BOB, 1), &refunded), FEE / 2);
+		// The tip is paid in full.
+		assert_eq!(pay_with_result(ALICE, tip, transfer(BOB, 1), &refunded), FEE / 2 + tip);
+		assert_eq!(pay_with_result(ALICE, 0, transfer(BOB, 1), &not_refunded), FEE);
+		assert_eq!(pay(ALICE, 0, transfer(BOB, 1)), FEE);
+	});
+}
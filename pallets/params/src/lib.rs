@@ -0,0 +1,106 @@
+//! # Params Pallet
+//!
+//! Holds runtime parameters which are chosen by the operator of a chain in its
+//! chain spec rather than being hard-coded into the runtime. The runtime reads
+//! them through `Get` implementations which are plugged into the configuration
+//! of other pallets.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::{Perbill, RuntimeDebug};
+
+pub use pallet::*;
+
+/// Whether the contracts pallet charges storage rent.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum RentMode {
+	/// No rent is charged and contracts are never evicted.
+	Disabled,
+	/// Rent is charged with the given fraction of the deposit shortfall per block.
+	Enabled {
+		fraction: Perbill,
+	},
+}
+
+impl RentMode {
+	/// The rent fraction which should be handed to the contracts pallet.
+	pub fn fraction(&self) -> Perbill {
+		match self {
+			RentMode::Disabled => Perbill::zero(),
+			RentMode::Enabled { fraction } => *fraction,
+		}
+	}
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::pallet_prelude::*;
+	use super::RentMode;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The rent model used when none was chosen in the chain spec.
+		type DefaultRentMode: Get<RentMode>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {}
+
+	#[pallet::type_value]
+	pub fn DefaultForRentMode<T: Config>() -> RentMode {
+		T::DefaultRentMode::get()
+	}
+
+	/// The rent model used by the contracts pallet.
+	#[pallet::storage]
+	#[pallet::getter(fn rent_mode)]
+	pub type ContractsRentMode<T> = StorageValue<_, RentMode, ValueQuery, DefaultForRentMode<T>>;
+
+	/// The minimum period between blocks in milliseconds, `None` for the runtime default.
+	#[pallet::storage]
+	#[pallet::getter(fn minimum_period)]
+	pub type TimestampMinimumPeriod<T> = StorageValue<_, u64>;
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig {
+		/// The rent model, `None` for `Config::DefaultRentMode`.
+		pub rent_mode: Option<RentMode>,
+		/// The minimum period between blocks in milliseconds, `None` for the runtime
+		/// default. Aura derives its slot duration from it as twice the minimum period,
+		/// so this also changes the block time of the chain.
+		pub minimum_period: Option<u64>,
+	}
+
+	#[cfg(feature = "std")]
+	impl Default for GenesisConfig {
+		fn default() -> Self {
+			Self {
+				rent_mode: None,
+				minimum_period: None,
+			}
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig {
+		fn build(&self) {
+			if let Some(rent_mode) = self.rent_mode {
+				ContractsRentMode::<T>::put(rent_mode);
+			}
+			if let Some(minimum_period) = self.minimum_period {
+				TimestampMinimumPeriod::<T>::put(minimum_period);
+			}
+		}
+	}
+}
@@ -0,0 +1,40 @@
+//! Benchmarks of the chain extension functions.
+//!
+//! The chain extension is not a pallet, so this module provides the `Pallet` and
+//! `Config` which `benchmarks!` expects. The functions are benchmarked without the
+//! contract calling them, whose cost is charged by the schedule of the contracts pallet.
+
+use crate::{chain_extension, Hash, Runtime};
+use frame_benchmarking::{benchmarks, impl_benchmark_test_suite};
+use frame_support::storage::migration::put_storage_value;
+use sp_std::prelude::*;
+
+pub struct Pallet<T: Config>(frame_system::Pallet<T>);
+pub trait Config: frame_system::Config {}
+
+impl Config for Runtime {}
+
+/// The number of blocks `pallet_randomness_collective_flip` keeps random material of.
+const RANDOM_MATERIAL_LEN: u8 = 81;
+
+benchmarks! {
+	fetch_random {
+		// The worst case: the random material of all blocks is available.
+		let material: Vec<Hash> = (0..RANDOM_MATERIAL_LEN).map(Hash::repeat_byte).collect();
+		put_storage_value(b"RandomnessCollectiveFlip", b"RandomMaterial", &[], material);
+		let subject = [1u8; 32];
+	}: {
+		chain_extension::random(&subject);
+	}
+
+	recommended_tip {
+	}: {
+		chain_extension::recommended_tip();
+	}
+}
+
+impl_benchmark_test_suite!(
+	Pallet,
+	crate::tests::ExtBuilder::default().build(),
+	crate::Runtime,
+);
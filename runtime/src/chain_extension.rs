@@ -0,0 +1,81 @@
+//! The chain extension which Canvas offers to contracts.
+//!
+//! Contracts call into it via `seal_call_chain_extension` with one of the
+//! function ids below.
+
+use codec::Encode;
+use frame_support::{log::error, traits::Randomness, weights::Weight};
+use pallet_contracts::chain_extension::{
+	ChainExtension, Environment, Ext, InitState, RetVal, SysConfig, UncheckedFrom,
+};
+use pallet_transaction_payment::Multiplier;
+use sp_runtime::{traits::Get, DispatchError, FixedPointNumber};
+
+/// Fetches a random value from `pallet_randomness_collective_flip`.
+///
+/// Input: a 32 byte subject. Output: the SCALE encoded random `Hash`.
+pub const FETCH_RANDOM: u32 = 1101;
+
+/// Returns the current fee multiplier and a tip recommendation for the caller's wallet.
+///
+/// Input: none. Output: the SCALE encoded `(Multiplier, Balance)`. This is purely
+/// advisory, contracts cannot pay tips themselves.
+pub const RECOMMENDED_TIP: u32 = 1102;
+
+/// Weight functions needed by the chain extension.
+pub trait WeightInfo {
+	fn fetch_random() -> Weight;
+	fn recommended_tip() -> Weight;
+}
+
+/// The benchmarked weights of the chain extension functions, see `crate::benchmarking`.
+type Weights = crate::weights::chain_extension::WeightInfo<crate::Runtime>;
+
+/// The random value `FETCH_RANDOM` returns for `subject`.
+pub(crate) fn random(subject: &[u8; 32]) -> crate::Hash {
+	crate::RandomnessCollectiveFlip::random(subject).0
+}
+
+/// The fee multiplier and the tip `RECOMMENDED_TIP` returns.
+pub(crate) fn recommended_tip() -> (Multiplier, crate::Balance) {
+	let multiplier = crate::TransactionPayment::next_fee_multiplier();
+	let tip = multiplier
+		.saturating_mul_int(crate::DefaultContractTip::get())
+		.max(crate::MinimumPriorityTip::get());
+	(multiplier, tip)
+}
+
+/// The chain extension of the Canvas runtime.
+pub struct CanvasExtension;
+
+impl ChainExtension<crate::Runtime> for CanvasExtension {
+	fn call<E: Ext>(func_id: u32, env: Environment<E, InitState>) -> Result<RetVal, DispatchError>
+	where
+		<E::T as SysConfig>::AccountId: UncheckedFrom<<E::T as SysConfig>::Hash> + AsRef<[u8]>,
+	{
+		match func_id {
+			FETCH_RANDOM => {
+				let mut env = env.buf_in_buf_out();
+				env.charge_weight(Weights::fetch_random())?;
+				let subject: [u8; 32] = env.read_as()?;
+				env.write(&random(&subject).encode(), false, None)
+					.map_err(|_| DispatchError::Other("ChainExtension failed to write the random value"))?;
+			},
+			RECOMMENDED_TIP => {
+				let mut env = env.buf_in_buf_out();
+				env.charge_weight(Weights::recommended_tip())?;
+				env.write(&recommended_tip().encode(), false, None)
+					.map_err(|_| DispatchError::Other("ChainExtension failed to write the tip"))?;
+			},
+			_ => {
+				error!(target: "runtime::contracts", "Called an unregistered `func_id`: {}", func_id);
+				return Err(DispatchError::Other("Unimplemented func_id"));
+			},
+		}
+		Ok(RetVal::Converging(0))
+	}
+
+	fn enabled() -> bool {
+		true
+	}
+}
@@ -0,0 +1,89 @@
+//! A signed extension which bounds the lifetime of transactions.
+
+use codec::{Decode, Encode};
+use frame_support::weights::DispatchInfo;
+use sp_runtime::{
+	generic::Era,
+	traits::{Get, SignedExtension},
+	transaction_validity::{
+		InvalidTransaction, TransactionValidity, TransactionValidityError,
+	},
+};
+use sp_std::fmt;
+use crate::{AccountId, Call, Hash, MaxEraPeriod, Runtime};
+
+/// The transaction is immortal or its era is longer than `MaxEraPeriod`.
+pub const ERA_TOO_LONG: u8 = 100;
+
+/// Check for transaction mortality.
+///
+/// Wraps `frame_system::CheckEra` and additionally rejects transactions which are
+/// immortal or whose era period exceeds `MaxEraPeriod`. This bounds the window in
+/// which a signed transaction can be (re-)submitted.
+///
+/// The encoding and identifier are the same as the ones of `frame_system::CheckEra`.
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct CheckMortality(Era);
+
+impl CheckMortality {
+	/// Utility constructor. Used only in client/factory code.
+	pub fn from(era: Era) -> Self {
+		Self(era)
+	}
+
+	fn inner(&self) -> frame_system::CheckEra<Runtime> {
+		frame_system::CheckEra::from(self.0)
+	}
+
+	fn ensure_bounded_lifetime(&self) -> Result<(), TransactionValidityError> {
+		match self.0 {
+			Era::Mortal(period, _) if period <= MaxEraPeriod::get() => Ok(()),
+			_ => Err(InvalidTransaction::Custom(ERA_TOO_LONG).into()),
+		}
+	}
+}
+
+impl fmt::Debug for CheckMortality {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "CheckMortality({:?})", self.0)
+	}
+
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut fmt::Formatter) -> fmt::Result {
+		Ok(())
+	}
+}
+
+impl SignedExtension for CheckMortality {
+	const IDENTIFIER: &'static str = "CheckEra";
+	type AccountId = AccountId;
+	type Call = Call;
+	type AdditionalSigned = Hash;
+	type Pre = ();
+
+	fn additional_signed(&self) -> Result<Self::AdditionalSigned, TransactionValidityError> {
+		self.inner().additional_signed()
+	}
+
+	fn validate(
+		&self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfo,
+		len: usize,
+	) -> TransactionValidity {
+		self.ensure_bounded_lifetime()?;
+		self.inner().validate(who, call, info, len)
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfo,
+		len: usize,
+	) -> Result<Self::Pre, TransactionValidityError> {
+		self.validate(who, call, info, len).map(|_| ())
+	}
+}
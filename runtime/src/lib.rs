@@ -6,6 +6,16 @@
 #[cfg(feature = "std")]
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+pub mod chain_extension;
+pub mod check_mortality;
+pub mod reserve_index;
+pub mod runtime_api;
+#[cfg(test)]
+mod tests;
+pub mod weights;
+
 use sp_std::prelude::*;
 use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
 use sp_runtime::{
@@ -18,6 +28,8 @@ use sp_runtime::traits::{
 	BlakeTwo256, Block as BlockT, Verify, IdentifyAccount, NumberFor,
 };
 use sp_api::impl_runtime_apis;
+use codec::{Decode, Encode};
+use frame_support::traits::{GetCallMetadata, GetPalletVersion, PalletVersion};
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use pallet_contracts::weights::WeightInfo;
 use pallet_grandpa::{AuthorityId as GrandpaId, AuthorityList as GrandpaAuthorityList};
@@ -32,12 +44,16 @@ use sp_version::NativeVersion;
 pub use sp_runtime::BuildStorage;
 pub use pallet_timestamp::Call as TimestampCall;
 pub use pallet_balances::Call as BalancesCall;
+pub use pallet_contracts::Call as ContractsCall;
+pub use pallet_contracts::Event as ContractsEvent;
+pub use pallet_contracts::Error as ContractsError;
 pub use sp_runtime::{Permill, Perbill};
+pub use pallet_params::RentMode;
 pub use frame_support::{
 	construct_runtime, parameter_types, StorageValue,
-	traits::{KeyOwnerProofSystem, Randomness},
+	traits::{Get, KeyOwnerProofSystem, Randomness},
 	weights::{
-		Weight, IdentityFee,
+		Weight, IdentityFee, GetDispatchInfo,
 		constants::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight, WEIGHT_PER_SECOND},
 		DispatchClass,
 	},
@@ -98,10 +114,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: create_runtime_str!("canvas"),
 	impl_name: create_runtime_str!("canvas"),
 	authoring_version: 1,
-	spec_version: 12,
+	spec_version: 13,
 	impl_version: 0,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	transaction_version: 2,
 };
 
 pub const MILLISECS_PER_BLOCK: u64 = 6000;
@@ -169,11 +185,48 @@ parameter_types! {
 		.avg_block_initialization(AVERAGE_ON_INITIALIZE_RATIO)
 		.build_or_panic();
 	pub const SS58Prefix: u16 = 42;
+	/// Transactions must be mortal with an era of at most this many blocks (~6.8 hours).
+	pub const MaxEraPeriod: u64 = 4096;
+}
+
+/// Rejects calls which dispatch a call into the contracts pallet on behalf of the origin.
+///
+/// The limits of `pallet_contracts_guard` are checked by a signed extension, which only
+/// sees the top-level call of an extrinsic. A contracts call wrapped into a `Multisig` or
+/// `Utility` call, at any depth, would bypass them, so the wrapping call is rejected
+/// instead. `Sudo` dispatches without this filter, root is trusted to respect the limits.
+pub struct BaseFilter;
+
+impl frame_support::traits::Filter<Call> for BaseFilter {
+	fn filter(call: &Call) -> bool {
+		!dispatches_contracts_call(call)
+	}
+}
+
+/// Whether `call` dispatches a call into the contracts pallet from within.
+fn dispatches_contracts_call(call: &Call) -> bool {
+	let is_or_dispatches = |call: &Call| matches!(call, Call::Contracts(_)) ||
+		dispatches_contracts_call(call);
+	match call {
+		Call::Multisig(pallet_multisig::Call::as_multi_threshold_1(_, call)) =>
+			is_or_dispatches(call),
+		// The call is only decoded by the final approval, which may also dispatch a call
+		// stored by an earlier approval. Calls are only stored through `as_multi`, so it is
+		// enough to check them here.
+		Call::Multisig(pallet_multisig::Call::as_multi(_, _, _, call, _, _)) =>
+			Call::decode(&mut &call[..]).map_or(false, |call| is_or_dispatches(&call)),
+		Call::Utility(pallet_utility::Call::batch(calls)) |
+		Call::Utility(pallet_utility::Call::batch_all(calls)) =>
+			calls.iter().any(is_or_dispatches),
+		Call::Utility(pallet_utility::Call::as_derivative(_, call)) =>
+			is_or_dispatches(call),
+		_ => false,
+	}
 }
 
 impl frame_system::Config for Runtime {
 	/// The basic call filter to use in dispatchable.
-	type BaseCallFilter = ();
+	type BaseCallFilter = BaseFilter;
 	/// Block & extrinsics weights: base values and limits.
 	type BlockWeights = RuntimeBlockWeights;
 	/// The maximum length of a block (in bytes).
@@ -226,6 +279,55 @@ impl pallet_aura::Config for Runtime {
 	type AuthorityId = AuraId;
 }
 
+/// Finds the account of the Aura authority which authored a block.
+///
+/// Aura authorities are sr25519 keys, so their account is the public key itself.
+pub struct AuraAccountAdapter;
+
+impl frame_support::traits::FindAuthor<AccountId> for AuraAccountAdapter {
+	fn find_author<'a, I>(digests: I) -> Option<AccountId>
+		where I: 'a + IntoIterator<Item = (frame_support::ConsensusEngineId, &'a [u8])>
+	{
+		let index = <Aura as frame_support::traits::FindAuthor<u32>>::find_author(digests)?;
+		let authority = Aura::authorities().get(index as usize)?.clone();
+		Some(sp_core::sr25519::Public::from(authority).into())
+	}
+}
+
+parameter_types! {
+	/// Minted to the author of every block on top of the fees. Zero disables the reward.
+	///
+	/// Canvas is a testnet whose authors are run by the maintainers, so there is nothing
+	/// to incentivize and no reward by default. It is read from the `:BlockReward:` key,
+	/// so a chain can enable it through `system.setStorage` without a runtime upgrade.
+	pub storage BlockReward: Balance = 0;
+}
+
+/// Mints `BlockReward` to the author of every block.
+pub struct RewardAuthor;
+
+impl pallet_authorship::EventHandler<AccountId, BlockNumber> for RewardAuthor {
+	fn note_author(author: AccountId) {
+		use frame_support::traits::Currency;
+
+		let reward = BlockReward::get();
+		if reward > 0 {
+			// Dropping the imbalance increases the total issuance.
+			let _ = Balances::deposit_creating(&author, reward);
+		}
+	}
+
+	fn note_uncle(_author: AccountId, _age: BlockNumber) {}
+}
+
+impl pallet_authorship::Config for Runtime {
+	type FindAuthor = AuraAccountAdapter;
+	// Aura and GRANDPA do not produce uncles.
+	type UncleGenerations = ();
+	type FilterUncle = ();
+	type EventHandler = RewardAuthor;
+}
+
 impl pallet_grandpa::Config for Runtime {
 	type Event = Event;
 	type Call = Call;
@@ -245,8 +347,18 @@ impl pallet_grandpa::Config for Runtime {
 	type WeightInfo = ();
 }
 
-parameter_types! {
-	pub const MinimumPeriod: u64 = SLOT_DURATION / 2;
+/// The minimum period between timestamps chosen in the chain spec, `SLOT_DURATION / 2`
+/// by default.
+///
+/// Aura derives its slot duration from this value (twice the minimum period), so
+/// changing it is only meant for chains which do not rely on the slot duration,
+/// e.g. manually sealed testnets.
+pub struct MinimumPeriod;
+
+impl Get<u64> for MinimumPeriod {
+	fn get() -> u64 {
+		Params::minimum_period().unwrap_or(SLOT_DURATION / 2)
+	}
 }
 
 impl pallet_timestamp::Config for Runtime {
@@ -260,6 +372,7 @@ impl pallet_timestamp::Config for Runtime {
 parameter_types! {
 	pub const ExistentialDeposit: u128 = 500;
 	pub const MaxLocks: u32 = 50;
+	pub const MaxReserves: u32 = 50;
 }
 
 impl pallet_balances::Config for Runtime {
@@ -272,8 +385,8 @@ impl pallet_balances::Config for Runtime {
 	type ExistentialDeposit = ExistentialDeposit;
 	type AccountStore = System;
 	type WeightInfo = ();
-	type MaxReserves = ();
-	type ReserveIdentifier = [u8; 8];
+	type MaxReserves = MaxReserves;
+	type ReserveIdentifier = runtime_api::ReserveIdentifier;
 }
 
 parameter_types! {
@@ -291,6 +404,48 @@ impl pallet_transaction_payment::Config for Runtime {
 	type FeeMultiplierUpdate = ();
 }
 
+parameter_types! {
+	/// Tips below this amount do not buy any priority.
+	pub const MinimumPriorityTip: Balance = MILLICENTS;
+	/// The tip the chain extension recommends to contract callers at a fee multiplier of one.
+	pub const DefaultContractTip: Balance = 2 * MILLICENTS;
+	pub const MinimumStake: Balance = 100 * DOLLARS;
+	pub const StakePriorityMultiplier: u64 = 2;
+	pub const UnstakeDelay: BlockNumber = 7 * DAYS;
+	// Reverted contract calls pay the full fee, like any other failed dispatch.
+	pub const FailureRefund: Perbill = Perbill::from_percent(0);
+}
+
+/// The calls into the contracts pallet, whose priority is boosted for staked accounts.
+pub struct ContractCalls;
+
+impl frame_support::traits::Filter<Call> for ContractCalls {
+	fn filter(call: &Call) -> bool {
+		matches!(call, Call::Contracts(_))
+	}
+}
+
+impl pallet_fees::Config for Runtime {
+	type Event = Event;
+	type MinimumPriorityTip = MinimumPriorityTip;
+	type Currency = Balances;
+	type BoostedCalls = ContractCalls;
+	type MinimumStake = MinimumStake;
+	type StakePriorityMultiplier = StakePriorityMultiplier;
+	type UnstakeDelay = UnstakeDelay;
+	type RefundedErrors = ContractReverts;
+	type FailureRefund = FailureRefund;
+}
+
+/// Contract calls which failed because the contract reverted (as opposed to trapped).
+pub struct ContractReverts;
+
+impl pallet_fees::RefundedErrors for ContractReverts {
+	fn is_refunded(error: &sp_runtime::DispatchError) -> bool {
+		*error == ContractsError::<Runtime>::ContractReverted.into()
+	}
+}
+
 parameter_types! {
 	pub TombstoneDeposit: Balance = deposit(
 		1,
@@ -299,19 +454,73 @@ parameter_types! {
 	pub DepositPerContract: Balance = TombstoneDeposit::get();
 	pub const DepositPerStorageByte: Balance = deposit(0, 1);
 	pub const DepositPerStorageItem: Balance = deposit(1, 0);
-	pub RentFraction: Perbill = Perbill::from_rational(1u32, 30 * DAYS);
 	pub const SurchargeReward: Balance = 150 * MILLICENTS;
 	pub const SignedClaimHandicap: u32 = 2;
-	// The lazy deletion runs inside on_initialize.
-	pub DeletionWeightLimit: Weight = AVERAGE_ON_INITIALIZE_RATIO *
+	// The lazy deletion runs inside on_initialize. `DeletionWeightLimit` scales this.
+	pub DeletionWeightBase: Weight = AVERAGE_ON_INITIALIZE_RATIO *
 		RuntimeBlockWeights::get().max_block;
 	// The weight needed for decoding the queue should be less or equal than a fifth
 	// of the overall weight dedicated to the lazy deletion.
-	pub DeletionQueueDepth: u32 = ((DeletionWeightLimit::get() / (
+	pub DeletionQueueDepth: u32 = ((DeletionWeightBase::get() / (
 			<Runtime as pallet_contracts::Config>::WeightInfo::on_initialize_per_queue_item(1) -
 			<Runtime as pallet_contracts::Config>::WeightInfo::on_initialize_per_queue_item(0)
 		)) / 5) as u32;
-	pub Schedule: pallet_contracts::Schedule<Runtime> = Default::default();
+	// Every topic is added to the bloom filter of the block, so keep them few.
+	pub const MaxEventTopics: u32 = 4;
+	// The linear memory of a contract, in pages of 64 KiB.
+	pub const MaxMemoryPages: u32 = 16;
+	pub Schedule: pallet_contracts::Schedule<Runtime> = {
+		let mut schedule = pallet_contracts::Schedule::<Runtime>::default();
+		// `seal_deposit_event` traps if a contract emits an event with more topics.
+		schedule.limits.event_topics = MaxEventTopics::get();
+		// Code which declares a larger maximum memory is rejected on upload.
+		schedule.limits.memory_pages = MaxMemoryPages::get();
+		schedule
+	};
+	// The debug buffer is only returned by RPC dry-runs, never stored on chain.
+	pub const MaxDebugBufferLen: u32 = 16 * 1024;
+}
+
+/// Appended to a debug buffer which was cut at `MaxDebugBufferLen`.
+const DEBUG_BUFFER_TRUNCATED: &[u8] = b"\n[debug buffer truncated]";
+
+/// Cuts a debug buffer returned by a dry-run down to `MaxDebugBufferLen` bytes.
+fn truncate_debug_buffer(buffer: &mut Vec<u8>) {
+	let max_len = MaxDebugBufferLen::get() as usize;
+	if buffer.len() > max_len {
+		buffer.truncate(max_len.saturating_sub(DEBUG_BUFFER_TRUNCATED.len()));
+		buffer.extend_from_slice(DEBUG_BUFFER_TRUNCATED);
+	}
+}
+
+/// The weight the lazy deletion of terminated contracts may use in a block.
+///
+/// Scales `DeletionWeightBase` by how full the previous block was: from twice the base
+/// after an empty block down to half of it after a full one. This deletes more while
+/// the chain is idle and leaves more room for extrinsics while it is busy.
+pub struct DeletionWeightLimit;
+
+impl Get<Weight> for DeletionWeightLimit {
+	fn get() -> Weight {
+		let base = DeletionWeightBase::get();
+		let utilization = Perbill::from_rational(
+			ContractsMetrics::last_block_weight(),
+			RuntimeBlockWeights::get().max_block,
+		);
+		let used = utilization * base;
+		base.saturating_mul(2).saturating_sub(used.saturating_add(used / 2))
+	}
+}
+
+/// The rent fraction according to the `RentMode` chosen in the chain spec.
+///
+/// A fraction of zero means that no rent is charged, hence contracts are never evicted.
+pub struct RentFraction;
+
+impl Get<Perbill> for RentFraction {
+	fn get() -> Perbill {
+		Params::rent_mode().fraction()
+	}
 }
 
 impl pallet_contracts::Config for Runtime {
@@ -329,20 +538,178 @@ impl pallet_contracts::Config for Runtime {
 	type SurchargeReward = SurchargeReward;
 	type WeightPrice = pallet_transaction_payment::Module<Self>;
 	type WeightInfo = pallet_contracts::weights::SubstrateWeight<Self>;
-	type ChainExtension = ();
+	type ChainExtension = chain_extension::CanvasExtension;
 	type DeletionQueueDepth = DeletionQueueDepth;
 	type DeletionWeightLimit = DeletionWeightLimit;
 	type Schedule = Schedule;
 	type CallStack = [pallet_contracts::Frame<Self>; 31];
 }
 
+parameter_types! {
+	pub const MaxInstantiationsPerBlock: u32 = 50;
+	pub const MaxEndowment: Balance = 1_000_000 * DOLLARS;
+	// A contract with less than the existential deposit would be reaped right away.
+	pub const MinEndowment: Balance = ExistentialDeposit::get();
+	pub const MaxSaltLength: u32 = 128;
+	pub const MaxCallInputLength: u32 = 16 * 1024;
+	pub const AllowCallValue: bool = true;
+	pub const MaxContractsPerAccount: u32 = 100;
+	// Canvas is open to any code; curated chains can restrict it to audited code.
+	pub const EnforceCodeWhitelist: bool = false;
+	// Accounts may instantiate in every block; `MaxContractsPerAccount` bounds their total.
+	pub const InstantiationCooldown: BlockNumber = 0;
+	/// Leave a quarter of the normal dispatch weight for non-contract extrinsics.
+	pub BlockGasLimit: Weight = RuntimeBlockWeights::get()
+		.get(DispatchClass::Normal)
+		.max_total
+		.unwrap_or(RuntimeBlockWeights::get().max_block)
+		.saturating_mul(3) / 4;
+	/// Trapped constructors are not penalized.
+	pub const TrapSlash: Perbill = Perbill::from_percent(0);
+}
+
+impl pallet_contracts_guard::Config for Runtime {
+	type Event = Event;
+	type MaxInstantiationsPerBlock = MaxInstantiationsPerBlock;
+	type MaxEndowment = MaxEndowment;
+	type MinEndowment = MinEndowment;
+	type MaxSaltLength = MaxSaltLength;
+	type MaxCallInputLength = MaxCallInputLength;
+	type AllowCallValue = AllowCallValue;
+	type MaxContractsPerAccount = MaxContractsPerAccount;
+	type EnforceCodeWhitelist = EnforceCodeWhitelist;
+	type InstantiationCooldown = InstantiationCooldown;
+	type BlockGasLimit = BlockGasLimit;
+	type OnContractCall = RecordContractCalls;
+	type OnInstantiation = RecordInstantiations;
+	type TrapSlash = TrapSlash;
+	// There is no treasury, so slashed endowments are burned.
+	type OnTrapSlash = ();
+}
+
+/// Counts the calls into contracts in `pallet_contracts_metrics` and reports the storage
+/// of the called contract, which a call may have changed or removed.
+///
+/// Only the calls of signed extrinsics are counted, calls from other contracts are not.
+pub struct RecordContractCalls;
+
+impl pallet_contracts_guard::OnContractCall<AccountId> for RecordContractCalls {
+	fn on_contract_call(address: &AccountId) {
+		ContractsMetrics::note_call(address);
+		ContractsMetrics::note_storage(address, contract_storage(address));
+	}
+
+	fn on_contract_call_weight() -> Weight {
+		// `CallCount`, `ContractInfoOf`, `ContractStorage` and `TotalStorage`.
+		RocksDbWeight::get().reads_writes(4, 3)
+	}
+}
+
+/// Records the provenance and the storage of instantiated contracts in
+/// `pallet_contracts_metrics`.
+pub struct RecordInstantiations;
+
+impl pallet_contracts_guard::OnInstantiation<AccountId> for RecordInstantiations {
+	fn on_instantiation(deployer: &AccountId, contract: &AccountId) {
+		ContractsMetrics::note_instantiation(deployer, contract);
+		ContractsMetrics::note_storage(contract, contract_storage(contract));
+	}
+
+	fn on_instantiation_weight() -> Weight {
+		// `Provenance`, `ContractInfoOf`, `ContractStorage` and `TotalStorage`.
+		RocksDbWeight::get().reads_writes(3, 3)
+	}
+}
+
+/// The storage used by the contract at `address` as `(bytes, items)`, `None` if it is
+/// not alive.
+fn contract_storage(address: &AccountId) -> Option<(u32, u32)> {
+	pallet_contracts::ContractInfoOf::<Runtime>::get(address)
+		.and_then(|info| info.get_alive())
+		.map(|info| (info.storage_size, info.pair_count))
+}
+
+/// Lists the alive contracts of `pallet_contracts` with their storage, from which
+/// `pallet_contracts_metrics` recomputes its totals on runtime upgrades.
+pub struct AliveContracts;
+
+impl pallet_contracts_metrics::AliveContracts<AccountId> for AliveContracts {
+	fn alive_contracts() -> Vec<(AccountId, (u32, u32))> {
+		pallet_contracts::ContractInfoOf::<Runtime>::iter()
+			.filter_map(|(address, info)| info
+				.get_alive()
+				.map(|info| (address, (info.storage_size, info.pair_count))))
+			.collect()
+	}
+}
+
+/// Picks the `ContractEmitted` events out of the events of a block.
+pub struct ContractEmittedEvents;
+
+impl pallet_contracts_metrics::ContractEvents<AccountId, Event> for ContractEmittedEvents {
+	fn contract_event(event: &Event) -> Option<(AccountId, Vec<u8>)> {
+		match event {
+			Event::Contracts(pallet_contracts::Event::ContractEmitted(address, data)) =>
+				Some((address.clone(), data.clone())),
+			_ => None,
+		}
+	}
+}
+
+parameter_types! {
+	// Enough for dapps to catch up after a reconnect without indexing the whole chain.
+	pub const EventRetention: BlockNumber = HOURS;
+	// A few times the events of a block full of contract calls.
+	pub const MaxIndexedEvents: u32 = 1024;
+}
+
+impl pallet_contracts_metrics::Config for Runtime {
+	type ContractEvents = ContractEmittedEvents;
+	type EventRetention = EventRetention;
+	type MaxIndexedEvents = MaxIndexedEvents;
+	type AliveContracts = AliveContracts;
+}
+
 impl pallet_sudo::Config for Runtime {
 	type Event = Event;
 	type Call = Call;
 }
 
+parameter_types! {
+	// One storage item; key size is 32; value is size 4+4+16+32 bytes = 56 bytes.
+	pub const DepositBase: Balance = deposit(1, 88);
+	// Additional storage item size of 32 bytes.
+	pub const DepositFactor: Balance = deposit(0, 32);
+	pub const MaxSignatories: u16 = 100;
+}
+
+impl pallet_multisig::Config for Runtime {
+	type Event = Event;
+	type Call = Call;
+	type Currency = Balances;
+	type DepositBase = DepositBase;
+	type DepositFactor = DepositFactor;
+	type MaxSignatories = MaxSignatories;
+	type WeightInfo = pallet_multisig::weights::SubstrateWeight<Runtime>;
+}
+
+impl pallet_utility::Config for Runtime {
+	type Event = Event;
+	type Call = Call;
+	type WeightInfo = pallet_utility::weights::SubstrateWeight<Runtime>;
+}
+
 impl pallet_randomness_collective_flip::Config for Runtime {}
 
+parameter_types! {
+	// Contracts are neither charged rent nor evicted unless the chain spec enables rent.
+	pub const DefaultRentMode: RentMode = RentMode::Disabled;
+}
+
+impl pallet_params::Config for Runtime {
+	type DefaultRentMode = DefaultRentMode;
+}
+
 // Create the runtime by composing the FRAME pallets that were previously configured.
 construct_runtime!(
 	pub enum Runtime where
@@ -359,6 +726,13 @@ construct_runtime!(
 		TransactionPayment: pallet_transaction_payment::{Pallet, Storage},
 		Sudo: pallet_sudo::{Pallet, Call, Config<T>, Storage, Event<T>},
 		Contracts: pallet_contracts::{Pallet, Call, Storage, Event<T>},
+		Params: pallet_params::{Pallet, Storage, Config},
+		ContractsGuard: pallet_contracts_guard::{Pallet, Call, Storage, Event<T>},
+		Fees: pallet_fees::{Pallet, Call, Storage, Event<T>},
+		Multisig: pallet_multisig::{Pallet, Call, Storage, Event<T>},
+		ContractsMetrics: pallet_contracts_metrics::{Pallet, Storage},
+		Authorship: pallet_authorship::{Pallet, Call, Storage},
+		Utility: pallet_utility::{Pallet, Call, Event},
 	}
 );
 
@@ -377,11 +751,15 @@ pub type SignedExtra = (
 	frame_system::CheckSpecVersion<Runtime>,
 	frame_system::CheckTxVersion<Runtime>,
 	frame_system::CheckGenesis<Runtime>,
-	frame_system::CheckEra<Runtime>,
+	check_mortality::CheckMortality,
 	frame_system::CheckNonce<Runtime>,
 	frame_system::CheckWeight<Runtime>,
-	pallet_transaction_payment::ChargeTransactionPayment<Runtime>
+	pallet_fees::ChargeTransactionPayment<Runtime>,
+	pallet_contracts_guard::CheckContractsCall<Runtime>,
 );
+/// An upper bound for the bytes a signed extrinsic adds to the encoded call: the
+/// version byte, the address, the signature and the `SignedExtra`.
+pub const SIGNED_EXTRINSIC_OVERHEAD: u32 = 128;
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
 /// Extrinsic type that has already been checked.
@@ -528,7 +906,11 @@ impl_runtime_apis! {
 			gas_limit: u64,
 			input_data: Vec<u8>,
 		) -> pallet_contracts_primitives::ContractExecResult {
-			Contracts::bare_call(origin, dest, value, gas_limit, input_data, CONTRACTS_DEBUG_OUTPUT)
+			let mut result = Contracts::bare_call(
+				origin, dest, value, gas_limit, input_data, CONTRACTS_DEBUG_OUTPUT,
+			);
+			truncate_debug_buffer(&mut result.debug_message);
+			result
 		}
 
 		fn instantiate(
@@ -540,7 +922,11 @@ impl_runtime_apis! {
 			salt: Vec<u8>,
 		) -> pallet_contracts_primitives::ContractInstantiateResult<AccountId, BlockNumber>
 		{
-			Contracts::bare_instantiate(origin, endowment, gas_limit, code, data, salt, true, CONTRACTS_DEBUG_OUTPUT)
+			let mut result = Contracts::bare_instantiate(
+				origin, endowment, gas_limit, code, data, salt, true, CONTRACTS_DEBUG_OUTPUT,
+			);
+			truncate_debug_buffer(&mut result.debug_message);
+			result
 		}
 
 		fn get_storage(
@@ -556,4 +942,279 @@ impl_runtime_apis! {
 			Contracts::rent_projection(address)
 		}
 	}
+
+	impl runtime_api::CanvasContractsApi<Block, AccountId, Balance, Hash> for Runtime {
+		fn code_hash(address: AccountId) -> Option<Hash> {
+			pallet_contracts::ContractInfoOf::<Runtime>::get(&address)
+				.and_then(|info| info.get_alive())
+				.map(|info| info.code_hash)
+		}
+
+		fn is_contract(address: AccountId) -> bool {
+			pallet_contracts::ContractInfoOf::<Runtime>::contains_key(&address)
+		}
+
+		fn deletion_queue() -> runtime_api::DeletionQueue {
+			// Each entry is a `DeletedContract { pair_count: u32, trie_id: Vec<u8> }`.
+			let queue = frame_support::storage::migration::get_storage_value::<Vec<(u32, Vec<u8>)>>(
+				b"Contracts", b"DeletionQueue", &[],
+			).unwrap_or_default();
+
+			runtime_api::DeletionQueue {
+				queue_depth: DeletionQueueDepth::get(),
+				len: queue.len() as u32,
+				trie_ids: queue.into_iter()
+					.take(runtime_api::MAX_DELETION_QUEUE_ENTRIES)
+					.map(|(_, trie_id)| trie_id)
+					.collect(),
+			}
+		}
+
+		fn estimate_instantiate(
+			origin: AccountId,
+			endowment: Balance,
+			gas_limit: u64,
+			code: pallet_contracts_primitives::Code<Hash>,
+			data: Vec<u8>,
+			salt: Vec<u8>,
+		) -> Result<runtime_api::InstantiateEstimate<Balance>, sp_runtime::DispatchError> {
+			use pallet_contracts_primitives::Code;
+
+			let dry_run = Contracts::bare_instantiate(
+				origin, endowment, gas_limit, code.clone(), data.clone(), salt.clone(), false, false,
+			);
+			dry_run.result?;
+			let gas_consumed = dry_run.gas_consumed;
+
+			let call = Call::Contracts(match code {
+				Code::Upload(code) => pallet_contracts::Call::instantiate_with_code(
+					endowment, gas_consumed, code.to_vec(), data, salt,
+				),
+				Code::Existing(hash) => pallet_contracts::Call::instantiate(
+					endowment, gas_consumed, hash, data, salt,
+				),
+			});
+			let len = call.encoded_size() as u32 + SIGNED_EXTRINSIC_OVERHEAD;
+			let fee = TransactionPayment::compute_fee(len, &call.get_dispatch_info(), 0);
+
+			Ok(runtime_api::InstantiateEstimate {
+				gas_consumed,
+				fee,
+				endowment,
+				total: fee.saturating_add(endowment),
+			})
+		}
+
+		fn call_count(address: AccountId) -> u64 {
+			ContractsMetrics::call_count(address)
+		}
+
+		fn provenance(address: AccountId) -> Option<(AccountId, BlockNumber)> {
+			ContractsMetrics::provenance(address)
+		}
+
+		fn recent_events(address: AccountId, count: u32) -> Vec<(BlockNumber, Vec<u8>)> {
+			ContractsMetrics::recent_events(&address, count.min(runtime_api::MAX_RECENT_EVENTS))
+		}
+
+		fn deposit_params() -> runtime_api::DepositParams<Balance> {
+			runtime_api::DepositParams {
+				deposit_per_contract: DepositPerContract::get(),
+				deposit_per_storage_byte: DepositPerStorageByte::get(),
+				deposit_per_storage_item: DepositPerStorageItem::get(),
+				tombstone_deposit: TombstoneDeposit::get(),
+			}
+		}
+
+		fn total_storage() -> runtime_api::StorageUsage {
+			let total = ContractsMetrics::total_storage();
+			runtime_api::StorageUsage {
+				contracts: total.contracts,
+				bytes: total.bytes,
+				items: total.items,
+			}
+		}
+
+		fn min_balance_to_instantiate(
+			code_len: u32,
+			storage_bytes: u32,
+		) -> runtime_api::MinimumBalance<Balance> {
+			let existential_deposit = ExistentialDeposit::get();
+			let code_len = code_len.min(Schedule::get().limits.code_size);
+
+			let mut endowment = Contracts::subsistence_threshold();
+			if let RentMode::Enabled { .. } = Params::rent_mode() {
+				// Storage values of ink! contracts are stored in 32 byte cells.
+				let items = Balance::from((storage_bytes.saturating_add(31) / 32).max(1));
+				let bytes = Balance::from(storage_bytes.saturating_add(code_len));
+				let deposit = DepositPerContract::get()
+					.saturating_add(items.saturating_mul(DepositPerStorageItem::get()))
+					.saturating_add(bytes.saturating_mul(DepositPerStorageByte::get()));
+				endowment = endowment.max(deposit);
+			}
+
+			let weights = RuntimeBlockWeights::get();
+			let gas_limit = weights.get(DispatchClass::Normal).max_total
+				.unwrap_or(weights.max_block) / 10;
+			// The call is built without code, the code is accounted for below. This avoids
+			// allocating `code_len` bytes for an estimate.
+			let call = Call::Contracts(pallet_contracts::Call::instantiate_with_code(
+				endowment, gas_limit, Vec::new(), Vec::new(), Vec::new(),
+			));
+			let len = (call.encoded_size() as u32)
+				// The empty code is encoded as a single byte for its length.
+				.saturating_sub(1)
+				.saturating_add(codec::Compact(code_len).encoded_size() as u32)
+				.saturating_add(code_len)
+				.saturating_add(SIGNED_EXTRINSIC_OVERHEAD);
+			let mut info = call.get_dispatch_info();
+			info.weight = <Runtime as pallet_contracts::Config>::WeightInfo::instantiate_with_code(
+				code_len / 1024, 0,
+			).saturating_add(gas_limit);
+			let fee = TransactionPayment::compute_fee(len, &info, 0);
+
+			runtime_api::MinimumBalance {
+				existential_deposit,
+				endowment,
+				fee,
+				total: existential_deposit.saturating_add(endowment).saturating_add(fee),
+			}
+		}
+	}
+
+	impl runtime_api::CanvasSystemApi<Block> for Runtime {
+		fn remaining_weight() -> Weight {
+			let weights = RuntimeBlockWeights::get();
+			let max_normal = weights.get(DispatchClass::Normal).max_total
+				.unwrap_or(weights.max_block);
+			max_normal.saturating_sub(*System::block_weight().get(DispatchClass::Normal))
+		}
+
+		fn decode_call(extrinsic: Vec<u8>) -> Option<runtime_api::CallSummary> {
+			let extrinsic = UncheckedExtrinsic::decode(&mut &extrinsic[..]).ok()?;
+			let metadata = extrinsic.function.get_call_metadata();
+			Some(runtime_api::CallSummary {
+				pallet: metadata.pallet_name.as_bytes().to_vec(),
+				call: metadata.function_name.as_bytes().to_vec(),
+				signed: extrinsic.signature.is_some(),
+				// The call is prefixed with the pallet and the call index.
+				args_len: extrinsic.function.encoded_size().saturating_sub(2) as u32,
+			})
+		}
+
+		fn pallet_versions() -> Vec<(Vec<u8>, Option<PalletVersion>)> {
+			macro_rules! pallet_versions {
+				($($pallet:ident),* $(,)?) => {
+					sp_std::vec![$(
+						(stringify!($pallet).as_bytes().to_vec(), $pallet::storage_version()),
+					)*]
+				}
+			}
+
+			// Keep in sync with `construct_runtime!`.
+			pallet_versions![
+				System,
+				RandomnessCollectiveFlip,
+				Timestamp,
+				Aura,
+				Grandpa,
+				Balances,
+				TransactionPayment,
+				Sudo,
+				Contracts,
+				Params,
+				ContractsGuard,
+				Fees,
+				Multisig,
+				ContractsMetrics,
+				Authorship,
+				Utility,
+			]
+		}
+
+		fn call_allowed(call: Vec<u8>) -> Option<bool> {
+			use frame_support::traits::Filter;
+
+			let call = Call::decode(&mut &call[..]).ok()?;
+			Some(<Runtime as frame_system::Config>::BaseCallFilter::filter(&call))
+		}
+
+		fn batch_weight(calls: Vec<u8>) -> Option<Weight> {
+			let calls = Vec::<Call>::decode(&mut &calls[..]).ok()?;
+			// The weight of `batch` includes the weight of every call and its own overhead.
+			Some(Call::Utility(pallet_utility::Call::batch(calls)).get_dispatch_info().weight)
+		}
+
+		fn next_timestamp() -> u64 {
+			Timestamp::now().saturating_add(Aura::slot_duration())
+		}
+	}
+
+	impl runtime_api::CanvasBalancesApi<Block, AccountId, Balance> for Runtime {
+		fn reserves_of(account: AccountId) -> Vec<(runtime_api::ReserveIdentifier, Balance)> {
+			Balances::reserves(&account)
+				.into_iter()
+				.map(|reserve| (reserve.id, reserve.amount))
+				.collect()
+		}
+
+		fn accounts_with_reserve(
+			id: runtime_api::ReserveIdentifier,
+			offset: u32,
+			limit: u32,
+		) -> Vec<(AccountId, Balance)> {
+			reserve_index::ReserveIndex::holders(
+				id,
+				offset,
+				limit.min(runtime_api::MAX_ACCOUNTS_WITH_RESERVE),
+			)
+		}
+
+		fn account_ledger(account: AccountId) -> runtime_api::AccountLedger<Balance> {
+			let data = System::account(&account).data;
+			runtime_api::AccountLedger {
+				free: data.free,
+				reserved: data.reserved,
+				misc_frozen: data.misc_frozen,
+				fee_frozen: data.fee_frozen,
+				locks: Balances::locks(&account)
+					.into_iter()
+					.map(|lock| (lock.id, lock.amount))
+					.collect(),
+				reserves: Balances::reserves(&account)
+					.into_iter()
+					.map(|reserve| (reserve.id, reserve.amount))
+					.collect(),
+			}
+		}
+
+		fn transfer_fee(value: Balance, keep_alive: bool) -> Balance {
+			let dest = Address::Id(AccountId::default());
+			let call = Call::Balances(if keep_alive {
+				BalancesCall::transfer_keep_alive(dest, value)
+			} else {
+				BalancesCall::transfer(dest, value)
+			});
+			let len = call.encoded_size() as u32 + SIGNED_EXTRINSIC_OVERHEAD;
+			TransactionPayment::compute_fee(len, &call.get_dispatch_info(), 0)
+		}
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	impl frame_benchmarking::Benchmark<Block> for Runtime {
+		fn dispatch_benchmark(
+			config: frame_benchmarking::BenchmarkConfig
+		) -> Result<Vec<frame_benchmarking::BenchmarkBatch>, sp_runtime::RuntimeString> {
+			use frame_benchmarking::{Benchmarking, BenchmarkBatch, add_benchmark, TrackedStorageKey};
+
+			let whitelist: Vec<TrackedStorageKey> = vec![];
+			let mut batches = Vec::<BenchmarkBatch>::new();
+			let params = (&config, &whitelist);
+
+			add_benchmark!(params, batches, canvas_chain_extension, benchmarking::Pallet::<Runtime>);
+
+			if batches.is_empty() { return Err("Benchmark not found for this pallet.".into()) }
+			Ok(batches)
+		}
+	}
 }
@@ -0,0 +1,93 @@
+//! An index of the accounts holding a named reserve.
+//!
+//! `pallet_balances` keeps the named reserves per account, so finding the holders of
+//! a reserve would need a scan over all accounts. `ReserveIndex` wraps the named
+//! reserve functions of `Balances` and keeps the holders of each identifier in
+//! `ReserveHolders`. Code of this runtime which reserves under a name must go through
+//! it for the index to be complete. No pallet of this runtime reserves under a name
+//! yet, so there are no earlier reserves which need to be indexed.
+
+use frame_support::{
+	generate_storage_alias,
+	storage::IterableStorageDoubleMap,
+	traits::{BalanceStatus, NamedReservableCurrency},
+	Blake2_128Concat, Twox64Concat,
+};
+use sp_runtime::{DispatchError, DispatchResult};
+use sp_std::prelude::*;
+use crate::{runtime_api::ReserveIdentifier, AccountId, Balance, Balances};
+
+generate_storage_alias!(
+	ReserveIndex,
+	ReserveHolders => DoubleMap<(Twox64Concat, ReserveIdentifier), (Blake2_128Concat, AccountId), ()>
+);
+
+type NegativeImbalance = pallet_balances::NegativeImbalance<crate::Runtime>;
+
+/// The named reserve functions of `Balances` which keep `ReserveHolders` up to date.
+pub struct ReserveIndex;
+
+impl ReserveIndex {
+	/// Reserve `value` of `who` under `id`, see `NamedReservableCurrency::reserve_named`.
+	pub fn reserve_named(id: &ReserveIdentifier, who: &AccountId, value: Balance) -> DispatchResult {
+		Balances::reserve_named(id, who, value)?;
+		Self::update(id, who);
+		Ok(())
+	}
+
+	/// Unreserve up to `value` of `who` under `id` and return the amount which could not
+	/// be unreserved, see `NamedReservableCurrency::unreserve_named`.
+	pub fn unreserve_named(id: &ReserveIdentifier, who: &AccountId, value: Balance) -> Balance {
+		let remaining = Balances::unreserve_named(id, who, value);
+		Self::update(id, who);
+		remaining
+	}
+
+	/// Slash up to `value` of the reserve of `who` under `id`, see
+	/// `NamedReservableCurrency::slash_reserved_named`.
+	pub fn slash_reserved_named(
+		id: &ReserveIdentifier,
+		who: &AccountId,
+		value: Balance,
+	) -> (NegativeImbalance, Balance) {
+		let slashed = Balances::slash_reserved_named(id, who, value);
+		Self::update(id, who);
+		slashed
+	}
+
+	/// Move up to `value` of the reserve of `slashed` under `id` to `beneficiary`, see
+	/// `NamedReservableCurrency::repatriate_reserved_named`.
+	pub fn repatriate_reserved_named(
+		id: &ReserveIdentifier,
+		slashed: &AccountId,
+		beneficiary: &AccountId,
+		value: Balance,
+		status: BalanceStatus,
+	) -> Result<Balance, DispatchError> {
+		let remaining = Balances::repatriate_reserved_named(id, slashed, beneficiary, value, status)?;
+		Self::update(id, slashed);
+		Self::update(id, beneficiary);
+		Ok(remaining)
+	}
+
+	/// Returns the holders of a reserve under `id` together with its amount, skipping the
+	/// first `offset` holders and returning at most `limit` of them.
+	pub fn holders(id: ReserveIdentifier, offset: u32, limit: u32) -> Vec<(AccountId, Balance)> {
+		ReserveHolders::iter_prefix(id)
+			.skip(offset as usize)
+			.take(limit as usize)
+			.map(|(account, ())| {
+				let amount = Balances::reserved_balance_named(&id, &account);
+				(account, amount)
+			})
+			.collect()
+	}
+
+	fn update(id: &ReserveIdentifier, who: &AccountId) {
+		if Balances::reserved_balance_named(id, who) == 0 {
+			ReserveHolders::remove(id, who);
+		} else {
+			ReserveHolders::insert(id, who, ());
+		}
+	}
+}
@@ -0,0 +1,258 @@
+//! Runtime APIs which are specific to Canvas.
+//!
+//! They complement the APIs provided by Substrate (e.g. `pallet_contracts_rpc_runtime_api`)
+//! with queries needed by the node's own RPC extensions.
+
+use codec::{Codec, Decode, Encode};
+use pallet_contracts_primitives::Code;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use frame_support::{traits::{LockIdentifier, PalletVersion}, weights::Weight};
+use sp_runtime::{DispatchError, RuntimeDebug};
+use sp_std::vec::Vec;
+
+/// The identifier of a named reserve in `pallet_balances`.
+pub type ReserveIdentifier = [u8; 8];
+
+/// The balance of an account and what restricts spending it.
+#[derive(Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub struct AccountLedger<Balance> {
+	/// The free balance.
+	pub free: Balance,
+	/// The reserved balance, the sum of the named and the anonymous reserves.
+	pub reserved: Balance,
+	/// The part of `free` which can not be spent other than for fees.
+	pub misc_frozen: Balance,
+	/// The part of `free` which can not be spent for fees.
+	pub fee_frozen: Balance,
+	/// The locks on the free balance as `(identifier, amount)` pairs.
+	pub locks: Vec<(LockIdentifier, Balance)>,
+	/// The named reserves as `(identifier, amount)` pairs.
+	pub reserves: Vec<(ReserveIdentifier, Balance)>,
+}
+
+/// The estimated cost of instantiating a contract.
+#[derive(Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub struct InstantiateEstimate<Balance> {
+	/// The gas consumed by a dry-run of the instantiation.
+	///
+	/// This is the `gas_limit` the fee is estimated for.
+	pub gas_consumed: u64,
+	/// The transaction fee, including the fee for `gas_consumed`.
+	pub fee: Balance,
+	/// The endowment transferred to the new contract.
+	pub endowment: Balance,
+	/// The minimum free balance the origin needs: `fee + endowment`.
+	pub total: Balance,
+}
+
+/// The minimum free balance needed to instantiate a contract.
+#[derive(Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub struct MinimumBalance<Balance> {
+	/// The balance which has to remain in the origin to keep it alive.
+	pub existential_deposit: Balance,
+	/// The minimum endowment of the contract.
+	///
+	/// This is the subsistence threshold and, while rent is enabled, at least the
+	/// deposit which makes the estimated storage rent free.
+	pub endowment: Balance,
+	/// The estimated transaction fee of the `instantiate_with_code` call.
+	pub fee: Balance,
+	/// The sum of all of the above.
+	pub total: Balance,
+}
+
+/// The contents of the contracts deletion queue.
+#[derive(Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct DeletionQueue {
+	/// The maximum number of entries the queue can hold (`DeletionQueueDepth`).
+	pub queue_depth: u32,
+	/// The number of entries in the queue.
+	pub len: u32,
+	/// The trie ids of the oldest entries, at most `MAX_DELETION_QUEUE_ENTRIES`.
+	pub trie_ids: Vec<Vec<u8>>,
+}
+
+/// The deposits the contracts pallet uses to calculate the rent.
+#[derive(Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub struct DepositParams<Balance> {
+	/// The deposit for every contract (`DepositPerContract`).
+	pub deposit_per_contract: Balance,
+	/// The deposit for every byte of contract storage (`DepositPerStorageByte`).
+	pub deposit_per_storage_byte: Balance,
+	/// The deposit for every item of contract storage (`DepositPerStorageItem`).
+	pub deposit_per_storage_item: Balance,
+	/// The deposit needed to leave a tombstone (`TombstoneDeposit`).
+	pub tombstone_deposit: Balance,
+}
+
+/// The storage used by all alive contracts.
+#[derive(Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct StorageUsage {
+	/// The number of alive contracts.
+	pub contracts: u32,
+	/// The total size of the values in contract storage in bytes.
+	pub bytes: u64,
+	/// The total number of items in contract storage.
+	pub items: u64,
+}
+
+/// The top-level call of a decoded extrinsic.
+#[derive(Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub struct CallSummary {
+	/// The name of the pallet as declared in `construct_runtime!`.
+	pub pallet: Vec<u8>,
+	/// The name of the dispatchable.
+	pub call: Vec<u8>,
+	/// Whether the extrinsic is signed.
+	pub signed: bool,
+	/// The length of the encoded call arguments.
+	pub args_len: u32,
+}
+
+/// The maximum number of accounts returned by `CanvasBalancesApi::accounts_with_reserve`.
+pub const MAX_ACCOUNTS_WITH_RESERVE: u32 = 1024;
+
+/// The maximum number of trie ids returned by `CanvasContractsApi::deletion_queue`.
+pub const MAX_DELETION_QUEUE_ENTRIES: usize = 256;
+
+/// The maximum number of events returned by `CanvasContractsApi::recent_events`.
+pub const MAX_RECENT_EVENTS: u32 = 256;
+
+sp_api::decl_runtime_apis! {
+	/// Contracts queries which are not covered by `pallet_contracts_rpc_runtime_api`.
+	pub trait CanvasContractsApi<AccountId, Balance, Hash> where
+		AccountId: Codec,
+		Balance: Codec,
+		Hash: Codec,
+	{
+		/// Returns the code hash of the contract at `address`.
+		///
+		/// Returns `None` if there is no alive contract at this address.
+		fn code_hash(address: AccountId) -> Option<Hash>;
+
+		/// Returns `true` if there is a contract at `address`.
+		///
+		/// Tombstones of evicted contracts count as contracts because they can be restored.
+		fn is_contract(address: AccountId) -> bool;
+
+		/// Returns the child tries of terminated contracts which are pending deletion.
+		fn deletion_queue() -> DeletionQueue;
+
+		/// Estimates the cost of an `instantiate_with_code` (or `instantiate` if `code`
+		/// refers to an existing code hash) transaction.
+		///
+		/// The instantiation is dry-run in order to determine the gas it consumes.
+		/// Returns the error of the dry-run if the instantiation fails.
+		fn estimate_instantiate(
+			origin: AccountId,
+			endowment: Balance,
+			gas_limit: u64,
+			code: Code<Hash>,
+			data: Vec<u8>,
+			salt: Vec<u8>,
+		) -> Result<InstantiateEstimate<Balance>, DispatchError>;
+
+		/// Returns the minimum free balance needed to upload and instantiate a contract
+		/// of `code_len` bytes which stores about `storage_bytes` bytes.
+		///
+		/// The fee assumes that the instantiation uses a tenth of the gas of a block.
+		/// A `code_len` above the maximum code size of the schedule is capped, as larger
+		/// code can not be uploaded anyway.
+		fn min_balance_to_instantiate(code_len: u32, storage_bytes: u32) -> MinimumBalance<Balance>;
+
+		/// Returns the number of successful calls into the contract at `address`.
+		///
+		/// Only the calls of signed extrinsics are counted, not those of other contracts.
+		fn call_count(address: AccountId) -> u64;
+
+		/// Returns the storage used by all alive contracts.
+		///
+		/// Tombstones and contracts in the deletion queue are not included. The totals are
+		/// maintained by `pallet_contracts_metrics`, so contracts which were neither
+		/// instantiated nor called by a signed extrinsic since it was added are missing.
+		fn total_storage() -> StorageUsage;
+
+		/// Returns the deployer of the contract at `address` and the number of the block
+		/// it was instantiated in.
+		///
+		/// Returns `None` for contracts which were not instantiated by a signed extrinsic
+		/// (e.g. by another contract) and for plain accounts.
+		fn provenance(address: AccountId) -> Option<(AccountId, crate::BlockNumber)>;
+
+		/// Returns up to `count` (capped at `MAX_RECENT_EVENTS`) events emitted by the
+		/// contract at `address` together with the number of their block, newest first.
+		///
+		/// Only events of the last `EventRetention` blocks are kept.
+		fn recent_events(address: AccountId, count: u32) -> Vec<(crate::BlockNumber, Vec<u8>)>;
+
+		/// Returns the deposits configured for the contracts pallet.
+		fn deposit_params() -> DepositParams<Balance>;
+	}
+
+	/// Balances queries which are not covered by the RPCs provided by Substrate.
+	pub trait CanvasBalancesApi<AccountId, Balance> where
+		AccountId: Codec,
+		Balance: Codec,
+	{
+		/// Returns the named reserves of `account` as `(identifier, amount)` pairs.
+		fn reserves_of(account: AccountId) -> Vec<(ReserveIdentifier, Balance)>;
+
+		/// Returns the accounts which hold a reserve under `id` together with its amount.
+		///
+		/// Only the reserves made through `reserve_index::ReserveIndex` are listed. The
+		/// accounts are returned in storage order, skipping the first `offset` matches
+		/// and returning at most `limit` (capped at `MAX_ACCOUNTS_WITH_RESERVE`) of them.
+		fn accounts_with_reserve(
+			id: ReserveIdentifier,
+			offset: u32,
+			limit: u32,
+		) -> Vec<(AccountId, Balance)>;
+
+		/// Returns the balances of `account` together with its locks and named reserves.
+		fn account_ledger(account: AccountId) -> AccountLedger<Balance>;
+
+		/// Returns the fee of a signed `transfer` (or `transfer_keep_alive`) of `value`.
+		///
+		/// The fee does not depend on the accounts involved, only the encoded length of
+		/// `value` and the weight of the call matter.
+		fn transfer_fee(value: Balance, keep_alive: bool) -> Balance;
+	}
+
+	/// System queries which are not covered by the RPCs provided by Substrate.
+	pub trait CanvasSystemApi {
+		/// Returns the weight which is still available to `Normal` dispatches in the
+		/// current block.
+		fn remaining_weight() -> Weight;
+
+		/// Decodes an `UncheckedExtrinsic` and returns the names of its top-level call.
+		///
+		/// Returns `None` if `extrinsic` is not a valid extrinsic of this runtime.
+		fn decode_call(extrinsic: Vec<u8>) -> Option<CallSummary>;
+
+		/// Returns the name and the storage version of every pallet in `construct_runtime!`.
+		///
+		/// The version is `None` if it was never written to storage.
+		fn pallet_versions() -> Vec<(Vec<u8>, Option<PalletVersion>)>;
+
+		/// Returns whether the `BaseCallFilter` currently permits the encoded `call`.
+		///
+		/// Returns `None` if `call` is not a valid call of this runtime.
+		fn call_allowed(call: Vec<u8>) -> Option<bool>;
+
+		/// Returns the weight of a `Utility::batch` of the encoded `Vec<Call>`.
+		///
+		/// Returns `None` if `calls` can not be decoded.
+		fn batch_weight(calls: Vec<u8>) -> Option<Weight>;
+
+		/// Returns the expected timestamp of the next block in milliseconds.
+		///
+		/// This is the timestamp of the current block plus the slot duration, the
+		/// interval at which Aura authors blocks.
+		fn next_timestamp() -> u64;
+	}
+}
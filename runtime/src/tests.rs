@@ -0,0 +1,1212 @@
+//! Tests of the runtime configuration, i.e. of how the pallets play together.
+
+use crate::*;
+use frame_support::{assert_ok, traits::GenesisBuild};
+use pallet_contracts::ContractInfoOf;
+use pallet_contracts_primitives::{Code, RentProjection};
+use sp_keyring::AccountKeyring;
+use sp_runtime::{
+	generic::{Era, SignedPayload},
+	traits::{Hash as _, Header as _},
+};
+
+/// Enough gas for any of the contracts used in the tests.
+pub const GAS_LIMIT: Weight = 10_000_000_000;
+
+/// A contract which does nothing but can be deployed and called.
+pub const DUMMY_CONTRACT: &str = r#"
+(module
+	(import "env" "memory" (memory 1 1))
+	(func (export "deploy"))
+	(func (export "call"))
+)
+"#;
+
+/// A contract which terminates itself when called, sending its balance to the caller.
+pub const SELF_DESTRUCT: &str = r#"
+(module
+	(import "seal0" "seal_caller" (func $seal_caller (param i32 i32)))
+	(import "seal0" "seal_terminate" (func $seal_terminate (param i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	;; [0, 4) the length of the caller's address
+	(data (i32.const 0) "\20")
+
+	(func (export "deploy"))
+	(func (export "call")
+		;; [4, 36) the caller's address
+		(call $seal_caller (i32.const 4) (i32.const 0))
+		(call $seal_terminate (i32.const 4) (i32.const 32))
+	)
+)
+"#;
+
+pub fn alice() -> AccountId {
+	AccountKeyring::Alice.to_account_id()
+}
+
+pub fn bob() -> AccountId {
+	AccountKeyring::Bob.to_account_id()
+}
+
+/// Builds the storage of a chain with `alice` and `bob` endowed.
+#[derive(Default)]
+pub struct ExtBuilder {
+	rent_mode: Option<RentMode>,
+	minimum_period: Option<u64>,
+	aura_authorities: Vec<AuraId>,
+}
+
+impl ExtBuilder {
+	pub fn rent_mode(mut self, rent_mode: RentMode) -> Self {
+		self.rent_mode = Some(rent_mode);
+		self
+	}
+
+	pub fn minimum_period(mut self, minimum_period: u64) -> Self {
+		self.minimum_period = Some(minimum_period);
+		self
+	}
+
+	pub fn aura_authorities(mut self, aura_authorities: Vec<AuraId>) -> Self {
+		self.aura_authorities = aura_authorities;
+		self
+	}
+
+	pub fn build(self) -> sp_io::TestExternalities {
+		let mut storage = frame_system::GenesisConfig::default()
+			.build_storage::<Runtime>()
+			.unwrap();
+		pallet_balances::GenesisConfig::<Runtime> {
+			balances: vec![(alice(), 1 << 60), (bob(), 1 << 60)],
+		}
+		.assimilate_storage(&mut storage)
+		.unwrap();
+		pallet_aura::GenesisConfig::<Runtime> {
+			authorities: self.aura_authorities,
+		}
+		.assimilate_storage(&mut storage)
+		.unwrap();
+		<pallet_params::GenesisConfig as GenesisBuild<Runtime>>::assimilate_storage(
+			&pallet_params::GenesisConfig {
+				rent_mode: self.rent_mode,
+				minimum_period: self.minimum_period,
+			},
+			&mut storage,
+		)
+		.unwrap();
+
+		let mut ext = sp_io::TestExternalities::new(storage);
+		// Events are not deposited in the genesis block.
+		ext.execute_with(|| System::set_block_number(1));
+		ext
+	}
+}
+
+/// Instantiates the contract given in the text format from `alice` and returns its address.
+pub fn instantiate(wat: &str, endowment: Balance) -> AccountId {
+	let code = wat::parse_str(wat).unwrap();
+	let code_hash = BlakeTwo256::hash(&code);
+	assert_ok!(Contracts::instantiate_with_code(
+		Origin::signed(alice()),
+		endowment,
+		GAS_LIMIT,
+		code,
+		vec![],
+		vec![],
+	));
+	Contracts::contract_address(&alice(), &code_hash, &[])
+}
+
+/// Starts block `number` on top of the current one, like a block author does.
+pub fn initialize_block(number: BlockNumber) {
+	Executive::initialize_block(&Header::new(
+		number,
+		Default::default(),
+		Default::default(),
+		[number as u8; 32].into(),
+		Default::default(),
+	));
+}
+
+/// The signed extensions of an extrinsic of `who` which is valid in the current block.
+pub fn signed_extra(who: &AccountId, tip: Balance) -> SignedExtra {
+	let period = MaxEraPeriod::get();
+	let current = u64::from(System::block_number()).saturating_sub(1);
+	(
+		frame_system::CheckSpecVersion::new(),
+		frame_system::CheckTxVersion::new(),
+		frame_system::CheckGenesis::new(),
+		check_mortality::CheckMortality::from(Era::mortal(period, current)),
+		frame_system::CheckNonce::from(System::account_nonce(who)),
+		frame_system::CheckWeight::new(),
+		pallet_fees::ChargeTransactionPayment::from(tip),
+		pallet_contracts_guard::CheckContractsCall::new(),
+	)
+}
+
+/// Signs `call` by `signer` like a wallet does.
+pub fn sign(signer: AccountKeyring, call: Call) -> UncheckedExtrinsic {
+	let who = signer.to_account_id();
+	let payload = SignedPayload::new(call, signed_extra(&who, 0)).unwrap();
+	let signature = payload.using_encoded(|payload| signer.sign(payload));
+	let (call, extra, _) = payload.deconstruct();
+	UncheckedExtrinsic::new_signed(call, Address::Id(who), signature.into(), extra)
+}
+
+/// Signs `call` by `signer` and applies it to the current block.
+pub fn apply(signer: AccountKeyring, call: Call) -> ApplyExtrinsicResult {
+	Executive::apply_extrinsic(sign(signer, call))
+}
+
+#[test]
+fn rent_defaults_to_disabled() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_eq!(Params::rent_mode(), RentMode::Disabled);
+		assert_eq!(RentFraction::get(), Perbill::zero());
+
+		let addr = instantiate(DUMMY_CONTRACT, Contracts::subsistence_threshold());
+		assert_eq!(Contracts::rent_projection(addr), Ok(RentProjection::NoEviction));
+	});
+}
+
+#[test]
+fn enabled_rent_evicts_contracts_eventually() {
+	let fraction = Perbill::from_rational(1u32, 30 * DAYS);
+	ExtBuilder::default().rent_mode(RentMode::Enabled { fraction }).build().execute_with(|| {
+		assert_eq!(RentFraction::get(), fraction);
+
+		let addr = instantiate(DUMMY_CONTRACT, Contracts::subsistence_threshold());
+		assert!(matches!(Contracts::rent_projection(addr), Ok(RentProjection::EvictionAt(_))));
+	});
+}
+
+#[test]
+fn disabled_rent_is_never_charged() {
+	ExtBuilder::default().rent_mode(RentMode::Disabled).build().execute_with(|| {
+		let addr = instantiate(DUMMY_CONTRACT, Contracts::subsistence_threshold());
+		let balance = Balances::free_balance(&addr);
+		assert_eq!(Contracts::rent_projection(addr.clone()), Ok(RentProjection::NoEviction));
+
+		System::set_block_number(365 * DAYS);
+		assert_ok!(Contracts::call(
+			Origin::signed(alice()),
+			addr.clone().into(),
+			0,
+			GAS_LIMIT,
+			vec![],
+		));
+		assert!(Contracts::claim_surcharge(Origin::signed(bob()), addr.clone(), None).is_err());
+
+		assert_eq!(Balances::free_balance(&addr), balance);
+		assert!(ContractInfoOf::<Runtime>::get(&addr).and_then(|c| c.get_alive()).is_some());
+	});
+}
+
+#[test]
+fn reserves_of_lists_the_named_reserves() {
+	use frame_support::traits::NamedReservableCurrency;
+	use runtime_api::runtime_decl_for_CanvasBalancesApi::CanvasBalancesApi;
+
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(Balances::reserve_named(b"feestake", &alice(), 100));
+		assert_ok!(Balances::reserve_named(b"deposits", &alice(), 200));
+
+		assert_eq!(Runtime::reserves_of(alice()), vec![(*b"deposits", 200), (*b"feestake", 100)]);
+		assert_eq!(Runtime::reserves_of(bob()), vec![]);
+	});
+}
+
+#[test]
+fn account_ledger_shows_locks_and_reserves() {
+	use frame_support::traits::{LockableCurrency, NamedReservableCurrency, WithdrawReasons};
+	use runtime_api::runtime_decl_for_CanvasBalancesApi::CanvasBalancesApi;
+
+	ExtBuilder::default().build().execute_with(|| {
+		Balances::set_lock(*b"feestake", &alice(), 1_000, WithdrawReasons::all());
+		Balances::set_lock(*b"vesting ", &alice(), 400, WithdrawReasons::TRANSFER);
+		assert_ok!(Balances::reserve_named(b"deposits", &alice(), 200));
+
+		let ledger = Runtime::account_ledger(alice());
+		assert_eq!(ledger.free, (1 << 60) - 200);
+		assert_eq!(ledger.reserved, 200);
+		assert_eq!(ledger.misc_frozen, 1_000);
+		assert_eq!(ledger.fee_frozen, 1_000);
+		assert_eq!(ledger.locks, vec![(*b"feestake", 1_000), (*b"vesting ", 400)]);
+		assert_eq!(ledger.reserves, vec![(*b"deposits", 200)]);
+
+		let ledger = Runtime::account_ledger(bob());
+		assert_eq!((ledger.free, ledger.reserved, ledger.misc_frozen), (1 << 60, 0, 0));
+		assert!(ledger.locks.is_empty() && ledger.reserves.is_empty());
+	});
+}
+
+#[test]
+fn accounts_with_reserve_lists_the_holders_of_a_reserve() {
+	use reserve_index::ReserveIndex;
+	use runtime_api::runtime_decl_for_CanvasBalancesApi::CanvasBalancesApi;
+
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(ReserveIndex::reserve_named(b"deposits", &alice(), 200));
+		assert_ok!(ReserveIndex::reserve_named(b"deposits", &bob(), 300));
+		assert_ok!(ReserveIndex::reserve_named(b"feestake", &bob(), 100));
+
+		let mut holders = Runtime::accounts_with_reserve(*b"deposits", 0, 10);
+		holders.sort();
+		let mut expected = vec![(alice(), 200), (bob(), 300)];
+		expected.sort();
+		assert_eq!(holders, expected);
+
+		assert_eq!(Runtime::accounts_with_reserve(*b"feestake", 0, 10), vec![(bob(), 100)]);
+		assert_eq!(Runtime::accounts_with_reserve(*b"deposits", 1, 10).len(), 1);
+		assert_eq!(Runtime::accounts_with_reserve(*b"deposits", 0, 1).len(), 1);
+		assert_eq!(Runtime::accounts_with_reserve(*b"unknown!", 0, 10), vec![]);
+	});
+}
+
+#[test]
+fn accounts_with_reserve_drops_the_accounts_which_released_their_reserve() {
+	use reserve_index::ReserveIndex;
+	use runtime_api::runtime_decl_for_CanvasBalancesApi::CanvasBalancesApi;
+
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(ReserveIndex::reserve_named(b"deposits", &alice(), 200));
+		assert_ok!(ReserveIndex::reserve_named(b"deposits", &bob(), 300));
+
+		assert_eq!(ReserveIndex::unreserve_named(b"deposits", &alice(), 100), 0);
+		let mut holders = Runtime::accounts_with_reserve(*b"deposits", 0, 10);
+		holders.sort();
+		let mut expected = vec![(alice(), 100), (bob(), 300)];
+		expected.sort();
+		assert_eq!(holders, expected);
+
+		assert_eq!(ReserveIndex::unreserve_named(b"deposits", &alice(), 100), 0);
+		let (_, not_slashed) = ReserveIndex::slash_reserved_named(b"deposits", &bob(), 300);
+		assert_eq!(not_slashed, 0);
+		assert_eq!(Runtime::accounts_with_reserve(*b"deposits", 0, 10), vec![]);
+	});
+}
+
+#[test]
+fn instantiate_estimate_matches_the_actual_charges() {
+	use runtime_api::runtime_decl_for_CanvasContractsApi::CanvasContractsApi;
+
+	ExtBuilder::default().build().execute_with(|| {
+		initialize_block(2);
+		let code = wat::parse_str(DUMMY_CONTRACT).unwrap();
+		let endowment = Contracts::subsistence_threshold();
+
+		let estimate = Runtime::estimate_instantiate(
+			alice(),
+			endowment,
+			GAS_LIMIT,
+			Code::Upload(code.clone().into()),
+			vec![],
+			vec![],
+		)
+		.unwrap();
+		assert_eq!(estimate.endowment, endowment);
+		assert_eq!(estimate.total, estimate.fee + endowment);
+
+		let before = Balances::free_balance(alice());
+		let call = Call::Contracts(ContractsCall::instantiate_with_code(
+			endowment,
+			estimate.gas_consumed,
+			code,
+			vec![],
+			vec![],
+		));
+		assert_eq!(apply(AccountKeyring::Alice, call), Ok(Ok(())));
+		let charged = before - Balances::free_balance(alice());
+
+		let difference = charged.max(estimate.total) - charged.min(estimate.total);
+		assert!(
+			difference <= estimate.total / 100,
+			"estimated {} but charged {}",
+			estimate.total,
+			charged,
+		);
+	});
+}
+
+/// Calls the chain extension function `func_id` with a 32 byte input of zeros.
+fn chain_extension_caller(func_id: u32) -> String {
+	format!(r#"
+(module
+	(import "seal0" "seal_call_chain_extension"
+		(func $seal_call_chain_extension (param i32 i32 i32 i32 i32) (result i32)))
+	(import "env" "memory" (memory 1 1))
+
+	;; [0, 4) the length of the output buffer
+	(data (i32.const 0) "\40")
+
+	(func (export "deploy"))
+	(func (export "call")
+		;; [4, 36) the input, [36, 100) the output buffer
+		(drop (call $seal_call_chain_extension
+			(i32.const {}) (i32.const 4) (i32.const 32) (i32.const 36) (i32.const 0)
+		))
+	)
+)
+"#, func_id)
+}
+
+/// Only checks that the extension charges what its `WeightInfo` says, not that these
+/// weights are adequate, see `weights::chain_extension`.
+#[test]
+fn chain_extension_charges_its_weight_info() {
+	use chain_extension::{WeightInfo as _, FETCH_RANDOM, RECOMMENDED_TIP};
+	type Weights = weights::chain_extension::WeightInfo<Runtime>;
+
+	ExtBuilder::default().build().execute_with(|| {
+		let endowment = Contracts::subsistence_threshold() * 10;
+		let gas_consumed = |func_id| {
+			let addr = instantiate(&chain_extension_caller(func_id), endowment);
+			let result = Contracts::bare_call(alice(), addr, 0, GAS_LIMIT, vec![], false);
+			assert!(result.result.is_ok());
+			result.gas_consumed
+		};
+
+		// Both contracts execute the same instructions and only differ in what the
+		// extension charges.
+		assert_eq!(
+			gas_consumed(FETCH_RANDOM) - gas_consumed(RECOMMENDED_TIP),
+			Weights::fetch_random() - Weights::recommended_tip(),
+		);
+	});
+}
+
+#[test]
+fn is_contract_distinguishes_contracts_from_accounts() {
+	use runtime_api::runtime_decl_for_CanvasContractsApi::CanvasContractsApi;
+
+	ExtBuilder::default().build().execute_with(|| {
+		let addr = instantiate(DUMMY_CONTRACT, Contracts::subsistence_threshold());
+
+		assert!(Runtime::is_contract(addr));
+		assert!(!Runtime::is_contract(bob()));
+	});
+}
+
+#[test]
+fn remaining_weight_decreases_with_consumed_weight() {
+	use runtime_api::runtime_decl_for_CanvasSystemApi::CanvasSystemApi;
+
+	ExtBuilder::default().build().execute_with(|| {
+		let before = Runtime::remaining_weight();
+		let max_normal = RuntimeBlockWeights::get()
+			.get(DispatchClass::Normal)
+			.max_total
+			.unwrap();
+		assert_eq!(before, max_normal - *System::block_weight().get(DispatchClass::Normal));
+
+		System::register_extra_weight_unchecked(1_000_000, DispatchClass::Normal);
+		assert_eq!(Runtime::remaining_weight(), before - 1_000_000);
+
+		// Operational weight is accounted separately.
+		System::register_extra_weight_unchecked(1_000_000, DispatchClass::Operational);
+		assert_eq!(Runtime::remaining_weight(), before - 1_000_000);
+	});
+}
+
+/// A call which instantiates `DUMMY_CONTRACT` from whoever dispatches it.
+fn instantiate_call() -> Call {
+	Call::Contracts(ContractsCall::instantiate_with_code(
+		Contracts::subsistence_threshold(),
+		GAS_LIMIT,
+		wat::parse_str(DUMMY_CONTRACT).unwrap(),
+		vec![],
+		vec![],
+	))
+}
+
+#[test]
+fn contracts_calls_nested_in_multisig_calls_are_filtered() {
+	use frame_support::traits::Filter;
+
+	ExtBuilder::default().build().execute_with(|| {
+		let threshold_1 = |call: Call| Call::Multisig(pallet_multisig::Call::as_multi_threshold_1(
+			vec![bob()],
+			Box::new(call),
+		));
+		let as_multi = |call: Call| Call::Multisig(pallet_multisig::Call::as_multi(
+			1,
+			vec![bob()],
+			None,
+			call.encode(),
+			false,
+			GAS_LIMIT,
+		));
+
+		assert!(BaseFilter::filter(&instantiate_call()));
+		assert!(!BaseFilter::filter(&threshold_1(instantiate_call())));
+		assert!(!BaseFilter::filter(&as_multi(instantiate_call())));
+		assert!(!BaseFilter::filter(&threshold_1(threshold_1(instantiate_call()))));
+		assert!(!BaseFilter::filter(&as_multi(threshold_1(instantiate_call()))));
+
+		let transfer = Call::Balances(pallet_balances::Call::transfer(bob().into(), 1));
+		assert!(BaseFilter::filter(&threshold_1(transfer.clone())));
+		assert!(BaseFilter::filter(&as_multi(transfer)));
+	});
+}
+
+#[test]
+fn multisig_cannot_instantiate_past_the_guard() {
+	ExtBuilder::default().build().execute_with(|| {
+		initialize_block(2);
+		let call = Call::Multisig(pallet_multisig::Call::as_multi_threshold_1(
+			vec![bob()],
+			Box::new(instantiate_call()),
+		));
+
+		assert!(matches!(apply(AccountKeyring::Alice, call), Ok(Err(_))));
+		assert_eq!(ContractInfoOf::<Runtime>::iter().count(), 0);
+	});
+}
+
+#[test]
+fn contracts_calls_nested_in_utility_calls_are_filtered() {
+	use frame_support::traits::Filter;
+
+	ExtBuilder::default().build().execute_with(|| {
+		let transfer = Call::Balances(pallet_balances::Call::transfer(bob().into(), 1));
+		let batch = |call: Call| Call::Utility(pallet_utility::Call::batch(
+			vec![transfer.clone(), call],
+		));
+		let batch_all = |call: Call| Call::Utility(pallet_utility::Call::batch_all(
+			vec![transfer.clone(), call],
+		));
+		let as_derivative = |call: Call| Call::Utility(pallet_utility::Call::as_derivative(
+			0,
+			Box::new(call),
+		));
+		let threshold_1 = |call: Call| Call::Multisig(pallet_multisig::Call::as_multi_threshold_1(
+			vec![bob()],
+			Box::new(call),
+		));
+
+		assert!(!BaseFilter::filter(&batch(instantiate_call())));
+		assert!(!BaseFilter::filter(&batch_all(instantiate_call())));
+		assert!(!BaseFilter::filter(&as_derivative(instantiate_call())));
+		assert!(!BaseFilter::filter(&batch(as_derivative(instantiate_call()))));
+		assert!(!BaseFilter::filter(&threshold_1(batch(instantiate_call()))));
+		assert!(!BaseFilter::filter(&batch(threshold_1(instantiate_call()))));
+
+		assert!(BaseFilter::filter(&batch(transfer.clone())));
+		assert!(BaseFilter::filter(&as_derivative(transfer.clone())));
+		assert!(BaseFilter::filter(&threshold_1(batch_all(transfer.clone()))));
+	});
+}
+
+#[test]
+fn utility_cannot_instantiate_past_the_guard() {
+	ExtBuilder::default().build().execute_with(|| {
+		initialize_block(2);
+		let call = Call::Utility(pallet_utility::Call::batch_all(vec![instantiate_call()]));
+
+		assert!(matches!(apply(AccountKeyring::Alice, call), Ok(Err(_))));
+		assert_eq!(ContractInfoOf::<Runtime>::iter().count(), 0);
+	});
+}
+
+#[test]
+fn batch_weight_is_the_weight_of_the_calls_plus_the_batch_overhead() {
+	use pallet_utility::WeightInfo as _;
+	use runtime_api::runtime_decl_for_CanvasSystemApi::CanvasSystemApi;
+
+	ExtBuilder::default().build().execute_with(|| {
+		let transfer = Call::Balances(pallet_balances::Call::transfer(bob().into(), 1));
+		let transfer_keep_alive =
+			Call::Balances(pallet_balances::Call::transfer_keep_alive(bob().into(), 1));
+		let calls_weight = transfer.get_dispatch_info().weight +
+			transfer_keep_alive.get_dispatch_info().weight;
+		let overhead = <Runtime as pallet_utility::Config>::WeightInfo::batch(2);
+
+		assert_eq!(
+			Runtime::batch_weight(vec![transfer, transfer_keep_alive].encode()),
+			Some(calls_weight + overhead),
+		);
+		assert_eq!(Runtime::batch_weight(vec![0xff]), None);
+	});
+}
+
+#[test]
+fn terminated_contracts_appear_in_the_deletion_queue() {
+	use runtime_api::runtime_decl_for_CanvasContractsApi::CanvasContractsApi;
+
+	ExtBuilder::default().build().execute_with(|| {
+		let queue = Runtime::deletion_queue();
+		assert_eq!((queue.len, queue.queue_depth), (0, DeletionQueueDepth::get()));
+
+		let addr = instantiate(SELF_DESTRUCT, Contracts::subsistence_threshold());
+		let trie_id = ContractInfoOf::<Runtime>::get(&addr)
+			.and_then(|c| c.get_alive())
+			.unwrap()
+			.trie_id;
+		assert_ok!(Contracts::call(Origin::signed(alice()), addr.into(), 0, GAS_LIMIT, vec![]));
+
+		let queue = Runtime::deletion_queue();
+		assert_eq!(queue.len, 1);
+		assert_eq!(queue.trie_ids, vec![trie_id]);
+	});
+}
+
+#[test]
+fn transactions_must_be_mortal_within_max_era_period() {
+	use check_mortality::{CheckMortality, ERA_TOO_LONG};
+	use sp_runtime::{traits::SignedExtension, transaction_validity::InvalidTransaction};
+
+	ExtBuilder::default().build().execute_with(|| {
+		let call = Call::System(frame_system::Call::remark(vec![]));
+		let info = call.get_dispatch_info();
+		let validate = |era| CheckMortality::from(era).validate(&alice(), &call, &info, 0);
+		let too_long = Err(InvalidTransaction::Custom(ERA_TOO_LONG).into());
+
+		assert!(validate(Era::mortal(MaxEraPeriod::get(), 0)).is_ok());
+		assert_eq!(validate(Era::mortal(MaxEraPeriod::get() * 2, 0)), too_long);
+		assert_eq!(validate(Era::Immortal), too_long);
+	});
+}
+
+#[test]
+fn check_mortality_is_identified_as_check_era() {
+	use sp_runtime::traits::SignedExtension;
+
+	// Wallets only know how to construct the payload of extensions they know.
+	assert_eq!(
+		<check_mortality::CheckMortality as SignedExtension>::IDENTIFIER,
+		<frame_system::CheckEra<Runtime> as SignedExtension>::IDENTIFIER,
+	);
+}
+
+/// Calls the chain extension function `RECOMMENDED_TIP` and returns its output.
+const RECOMMENDED_TIP_FORWARDER: &str = r#"
+(module
+	(import "seal0" "seal_call_chain_extension"
+		(func $seal_call_chain_extension (param i32 i32 i32 i32 i32) (result i32)))
+	(import "seal0" "seal_return" (func $seal_return (param i32 i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	;; [0, 4) the length of the output buffer
+	(data (i32.const 0) "\40")
+
+	(func (export "deploy"))
+	(func (export "call")
+		;; [4, 68) the output buffer
+		(drop (call $seal_call_chain_extension
+			(i32.const 1102) (i32.const 0) (i32.const 0) (i32.const 4) (i32.const 0)
+		))
+		(call $seal_return (i32.const 0) (i32.const 4) (i32.load (i32.const 0)))
+	)
+)
+"#;
+
+#[test]
+fn recommended_tip_follows_the_fee_multiplier() {
+	use frame_support::storage::migration::put_storage_value;
+	use sp_runtime::FixedPointNumber;
+
+	ExtBuilder::default().build().execute_with(|| {
+		let addr = instantiate(RECOMMENDED_TIP_FORWARDER, Contracts::subsistence_threshold());
+		let recommendation = |multiplier: Multiplier| {
+			put_storage_value(b"TransactionPayment", b"NextFeeMultiplier", &[], multiplier);
+			let result = Contracts::bare_call(alice(), addr.clone(), 0, GAS_LIMIT, vec![], false);
+			<(Multiplier, Balance)>::decode(&mut &result.result.unwrap().data[..]).unwrap()
+		};
+
+		let low = Multiplier::saturating_from_integer(1);
+		assert_eq!(recommendation(low), (low, DefaultContractTip::get().max(MinimumPriorityTip::get())));
+
+		let high = Multiplier::saturating_from_integer(1_000);
+		assert_eq!(recommendation(high), (high, DefaultContractTip::get() * 1_000));
+		assert!(DefaultContractTip::get() * 1_000 > MinimumPriorityTip::get());
+	});
+}
+
+#[test]
+fn decode_call_names_the_call_of_an_extrinsic() {
+	use runtime_api::{runtime_decl_for_CanvasSystemApi::CanvasSystemApi, CallSummary};
+
+	ExtBuilder::default().build().execute_with(|| {
+		let transfer = Call::Balances(pallet_balances::Call::transfer(bob().into(), 1_000));
+		let summary = |signed| Some(CallSummary {
+			pallet: b"Balances".to_vec(),
+			call: b"transfer".to_vec(),
+			signed,
+			args_len: transfer.encoded_size() as u32 - 2,
+		});
+
+		assert_eq!(
+			Runtime::decode_call(sign(AccountKeyring::Alice, transfer.clone()).encode()),
+			summary(true),
+		);
+		assert_eq!(
+			Runtime::decode_call(UncheckedExtrinsic::new_unsigned(transfer.clone()).encode()),
+			summary(false),
+		);
+		assert_eq!(Runtime::decode_call(vec![1, 2, 3]), None);
+	});
+}
+
+/// Prints a debug message of as many bytes as passed in the input, at most a page.
+const DEBUG_PRINTER: &str = r#"
+(module
+	(import "seal0" "seal_input" (func $seal_input (param i32 i32)))
+	(import "seal0" "seal_debug_message" (func $seal_debug_message (param i32 i32) (result i32)))
+	(import "env" "memory" (memory 1 1))
+
+	;; [0, 4) the length of the input buffer
+	(data (i32.const 0) "\04")
+
+	(func (export "deploy"))
+	(func (export "call")
+		;; [4, 8) the input, a little endian u32
+		(call $seal_input (i32.const 4) (i32.const 0))
+		(drop (call $seal_debug_message (i32.const 8) (i32.load (i32.const 4))))
+	)
+)
+"#;
+
+#[test]
+fn debug_buffers_are_truncated_at_max_debug_buffer_len() {
+	use pallet_contracts_rpc_runtime_api::runtime_decl_for_ContractsApi::ContractsApi;
+
+	ExtBuilder::default().build().execute_with(|| {
+		let addr = instantiate(DEBUG_PRINTER, Contracts::subsistence_threshold());
+		let debug_message = |len: u32| {
+			Runtime::call(alice(), addr.clone(), 0, GAS_LIMIT, len.encode()).debug_message
+		};
+		let max_len = MaxDebugBufferLen::get();
+
+		assert_eq!(debug_message(max_len).len(), max_len as usize);
+		assert!(!debug_message(max_len).ends_with(DEBUG_BUFFER_TRUNCATED));
+
+		let truncated = debug_message(max_len + 1);
+		assert_eq!(truncated.len(), max_len as usize);
+		assert!(truncated.ends_with(DEBUG_BUFFER_TRUNCATED));
+	});
+}
+
+#[test]
+fn min_balance_to_instantiate_is_enough_to_instantiate() {
+	use runtime_api::runtime_decl_for_CanvasContractsApi::CanvasContractsApi;
+
+	ExtBuilder::default().build().execute_with(|| {
+		initialize_block(2);
+		let code = wat::parse_str(DUMMY_CONTRACT).unwrap();
+		let minimum = Runtime::min_balance_to_instantiate(code.len() as u32, 0);
+		assert_eq!(
+			minimum.total,
+			minimum.existential_deposit + minimum.endowment + minimum.fee,
+		);
+
+		// An account which holds just the minimum can pay for the instantiation the
+		// estimate assumes and stays alive.
+		let charlie = AccountKeyring::Charlie.to_account_id();
+		Balances::make_free_balance_be(&charlie, minimum.total);
+		let weights = RuntimeBlockWeights::get();
+		let gas_limit = weights.get(DispatchClass::Normal).max_total.unwrap() / 10;
+		let call = Call::Contracts(ContractsCall::instantiate_with_code(
+			minimum.endowment,
+			gas_limit,
+			code.clone(),
+			vec![],
+			vec![],
+		));
+		assert_eq!(apply(AccountKeyring::Charlie, call), Ok(Ok(())));
+		assert!(Balances::free_balance(&charlie) >= minimum.existential_deposit);
+		assert!(Runtime::is_contract(Contracts::contract_address(
+			&charlie,
+			&BlakeTwo256::hash(&code),
+			&[],
+		)));
+	});
+}
+
+#[test]
+fn min_balance_to_instantiate_caps_the_code_len() {
+	use runtime_api::runtime_decl_for_CanvasContractsApi::CanvasContractsApi;
+
+	ExtBuilder::default().build().execute_with(|| {
+		let code_size = Schedule::get().limits.code_size;
+		assert_eq!(
+			Runtime::min_balance_to_instantiate(u32::MAX, 0),
+			Runtime::min_balance_to_instantiate(code_size, 0),
+		);
+		assert!(
+			Runtime::min_balance_to_instantiate(code_size, 0).fee >
+				Runtime::min_balance_to_instantiate(0, 0).fee,
+		);
+	});
+}
+
+#[test]
+fn pallet_versions_are_set_at_genesis() {
+	use frame_support::traits::GetPalletVersion;
+	use runtime_api::runtime_decl_for_CanvasSystemApi::CanvasSystemApi;
+
+	// The genesis of a chain spec, which also stores the pallet versions.
+	let storage = GenesisConfig {
+		system: SystemConfig { code: vec![], changes_trie_config: None },
+		balances: BalancesConfig { balances: vec![(alice(), 1 << 60)] },
+		aura: AuraConfig { authorities: vec![] },
+		grandpa: GrandpaConfig { authorities: vec![] },
+		sudo: SudoConfig { key: alice() },
+		params: ParamsConfig { rent_mode: None, minimum_period: None },
+	}
+	.build_storage()
+	.unwrap();
+
+	sp_io::TestExternalities::new(storage).execute_with(|| {
+		let versions = Runtime::pallet_versions();
+		assert_eq!(
+			versions.iter().find(|(pallet, _)| pallet == b"Contracts").map(|(_, v)| *v),
+			Some(Some(<Contracts as GetPalletVersion>::current_version())),
+		);
+		assert!(versions.iter().all(|(_, version)| version.is_some()));
+	});
+}
+
+#[test]
+fn call_count_counts_successful_calls() {
+	use runtime_api::runtime_decl_for_CanvasContractsApi::CanvasContractsApi;
+
+	ExtBuilder::default().build().execute_with(|| {
+		initialize_block(2);
+		let called = instantiate(DUMMY_CONTRACT, Contracts::subsistence_threshold());
+		let code = wat::parse_str(SELF_DESTRUCT).unwrap();
+		let never_called = Contracts::contract_address(&alice(), &BlakeTwo256::hash(&code), &[]);
+		assert_ok!(Contracts::instantiate_with_code(
+			Origin::signed(alice()),
+			Contracts::subsistence_threshold(),
+			GAS_LIMIT,
+			code,
+			vec![],
+			vec![],
+		));
+
+		let call = Call::Contracts(ContractsCall::call(called.clone().into(), 0, GAS_LIMIT, vec![]));
+		for _ in 0..3 {
+			assert_eq!(apply(AccountKeyring::Bob, call.clone()), Ok(Ok(())));
+		}
+
+		assert_eq!(Runtime::call_count(called), 3);
+		assert_eq!(Runtime::call_count(never_called), 0);
+	});
+}
+
+/// A contract which stores four bytes under one key when deployed and terminates itself
+/// when called, like `SELF_DESTRUCT`.
+const STORING_SELF_DESTRUCT: &str = r#"
+(module
+	(import "seal0" "seal_set_storage" (func $seal_set_storage (param i32 i32 i32)))
+	(import "seal0" "seal_caller" (func $seal_caller (param i32 i32)))
+	(import "seal0" "seal_terminate" (func $seal_terminate (param i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	;; [0, 4) the length of the caller's address
+	(data (i32.const 0) "\20")
+
+	;; [36, 68) the storage key
+	(data (i32.const 36)
+		"\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01"
+		"\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01"
+	)
+
+	;; [68, 72) the stored value
+	(data (i32.const 68) "\de\ad\be\ef")
+
+	(func (export "deploy")
+		(call $seal_set_storage (i32.const 36) (i32.const 68) (i32.const 4))
+	)
+	(func (export "call")
+		;; [4, 36) the caller's address
+		(call $seal_caller (i32.const 4) (i32.const 0))
+		(call $seal_terminate (i32.const 4) (i32.const 32))
+	)
+)
+"#;
+
+#[test]
+fn total_storage_follows_instantiations_and_terminations() {
+	use runtime_api::{runtime_decl_for_CanvasContractsApi::CanvasContractsApi, StorageUsage};
+
+	ExtBuilder::default().build().execute_with(|| {
+		initialize_block(2);
+		assert_eq!(Runtime::total_storage(), StorageUsage { contracts: 0, bytes: 0, items: 0 });
+
+		let code = wat::parse_str(STORING_SELF_DESTRUCT).unwrap();
+		let code_hash = BlakeTwo256::hash(&code);
+		let endowment = 10 * Contracts::subsistence_threshold();
+		let upload = Call::Contracts(ContractsCall::instantiate_with_code(
+			endowment,
+			GAS_LIMIT,
+			code,
+			vec![],
+			vec![1],
+		));
+		let instantiate = Call::Contracts(ContractsCall::instantiate(
+			endowment,
+			GAS_LIMIT,
+			code_hash,
+			vec![],
+			vec![2],
+		));
+		assert_eq!(apply(AccountKeyring::Alice, upload), Ok(Ok(())));
+		assert_eq!(apply(AccountKeyring::Alice, instantiate), Ok(Ok(())));
+		assert_eq!(Runtime::total_storage(), StorageUsage { contracts: 2, bytes: 8, items: 2 });
+
+		let first = Contracts::contract_address(&alice(), &code_hash, &[1]);
+		let terminate = Call::Contracts(ContractsCall::call(first.into(), 0, GAS_LIMIT, vec![]));
+		assert_eq!(apply(AccountKeyring::Alice, terminate), Ok(Ok(())));
+		assert_eq!(Runtime::total_storage(), StorageUsage { contracts: 1, bytes: 4, items: 1 });
+	});
+}
+
+#[test]
+fn runtime_upgrades_count_the_contracts_which_were_not_reported() {
+	use frame_support::traits::OnRuntimeUpgrade;
+	use runtime_api::{runtime_decl_for_CanvasContractsApi::CanvasContractsApi, StorageUsage};
+
+	ExtBuilder::default().build().execute_with(|| {
+		// Dispatched without the guard, like the contracts instantiated before the metrics.
+		instantiate(STORING_SELF_DESTRUCT, 10 * Contracts::subsistence_threshold());
+		assert_eq!(Runtime::total_storage(), StorageUsage { contracts: 0, bytes: 0, items: 0 });
+
+		ContractsMetrics::on_runtime_upgrade();
+		assert_eq!(Runtime::total_storage(), StorageUsage { contracts: 1, bytes: 4, items: 1 });
+	});
+}
+
+/// A contract which emits an event without topics whose data is the input of the call.
+const INPUT_EMITTER: &str = r#"
+(module
+	(import "seal0" "seal_input" (func $seal_input (param i32 i32)))
+	(import "seal0" "seal_deposit_event" (func $seal_deposit_event (param i32 i32 i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	;; [0, 4) the length of the input buffer
+	(data (i32.const 0) "\40")
+
+	(func (export "deploy"))
+	(func (export "call")
+		;; [4, 68) the input
+		(call $seal_input (i32.const 4) (i32.const 0))
+		(call $seal_deposit_event
+			(i32.const 0) ;; topics_ptr
+			(i32.const 0) ;; topics_len
+			(i32.const 4) ;; data_ptr
+			(i32.load (i32.const 0)) ;; data_len
+		)
+	)
+)
+"#;
+
+#[test]
+fn recent_events_are_indexed_over_several_blocks_newest_first() {
+	use frame_support::traits::OnFinalize;
+	use runtime_api::runtime_decl_for_CanvasContractsApi::CanvasContractsApi;
+
+	ExtBuilder::default().build().execute_with(|| {
+		let addr = instantiate(INPUT_EMITTER, Contracts::subsistence_threshold());
+
+		for number in 2..=4 {
+			initialize_block(number);
+			assert_ok!(Contracts::call(
+				Origin::signed(alice()),
+				addr.clone().into(),
+				0,
+				GAS_LIMIT,
+				vec![number as u8],
+			));
+			<ContractsMetrics as OnFinalize<BlockNumber>>::on_finalize(number);
+		}
+
+		assert_eq!(
+			Runtime::recent_events(addr.clone(), 10),
+			vec![(4, vec![4]), (3, vec![3]), (2, vec![2])],
+		);
+		assert_eq!(Runtime::recent_events(addr, 2), vec![(4, vec![4]), (3, vec![3])]);
+	});
+}
+
+#[test]
+fn transfer_fee_matches_the_fee_of_a_signed_transfer() {
+	use runtime_api::runtime_decl_for_CanvasBalancesApi::CanvasBalancesApi;
+
+	ExtBuilder::default().build().execute_with(|| {
+		for &keep_alive in &[false, true] {
+			let value = 1_000 * DOLLARS;
+			let dest = Address::Id(AccountId::default());
+			let call = Call::Balances(if keep_alive {
+				BalancesCall::transfer_keep_alive(dest, value)
+			} else {
+				BalancesCall::transfer(dest, value)
+			});
+			let call_len = call.encoded_size() as u32;
+			let xt = sign(AccountKeyring::Alice, call);
+			let len = xt.encoded_size() as u32;
+			let queried = TransactionPayment::query_info(xt, len).partial_fee;
+
+			// `transfer_fee` charges `SIGNED_EXTRINSIC_OVERHEAD` bytes for the signature and
+			// the signed extensions, an upper bound of what the actual extrinsic needs.
+			let overhead = len - call_len;
+			assert!(overhead <= SIGNED_EXTRINSIC_OVERHEAD);
+			let fee = Runtime::transfer_fee(value, keep_alive);
+			assert_eq!(
+				fee - queried,
+				Balance::from(SIGNED_EXTRINSIC_OVERHEAD - overhead) * TransactionByteFee::get(),
+			);
+		}
+	});
+}
+
+#[test]
+fn deposit_params_are_the_deposits_of_the_contracts_pallet() {
+	use pallet_contracts::Config as ContractsConfig;
+	use runtime_api::{runtime_decl_for_CanvasContractsApi::CanvasContractsApi, DepositParams};
+
+	ExtBuilder::default().build().execute_with(|| {
+		assert_eq!(
+			Runtime::deposit_params(),
+			DepositParams {
+				deposit_per_contract: <Runtime as ContractsConfig>::DepositPerContract::get(),
+				deposit_per_storage_byte: <Runtime as ContractsConfig>::DepositPerStorageByte::get(),
+				deposit_per_storage_item: <Runtime as ContractsConfig>::DepositPerStorageItem::get(),
+				tombstone_deposit: <Runtime as ContractsConfig>::TombstoneDeposit::get(),
+			},
+		);
+		assert_eq!(Runtime::deposit_params().deposit_per_storage_byte, deposit(0, 1));
+		assert_eq!(Runtime::deposit_params().deposit_per_storage_item, deposit(1, 0));
+	});
+}
+
+#[test]
+fn call_allowed_applies_the_base_call_filter() {
+	use runtime_api::runtime_decl_for_CanvasSystemApi::CanvasSystemApi;
+
+	ExtBuilder::default().build().execute_with(|| {
+		let transfer = Call::Balances(pallet_balances::Call::transfer(bob().into(), 1));
+		let threshold_1 = |call: Call| Call::Multisig(pallet_multisig::Call::as_multi_threshold_1(
+			vec![bob()],
+			Box::new(call),
+		));
+		let batch = |call: Call| Call::Utility(pallet_utility::Call::batch(vec![call]));
+
+		assert_eq!(Runtime::call_allowed(transfer.encode()), Some(true));
+		assert_eq!(Runtime::call_allowed(instantiate_call().encode()), Some(true));
+		assert_eq!(Runtime::call_allowed(threshold_1(transfer.clone()).encode()), Some(true));
+		assert_eq!(Runtime::call_allowed(batch(transfer).encode()), Some(true));
+
+		assert_eq!(Runtime::call_allowed(threshold_1(instantiate_call()).encode()), Some(false));
+		assert_eq!(Runtime::call_allowed(batch(instantiate_call()).encode()), Some(false));
+		assert_eq!(Runtime::call_allowed(vec![0xff, 0xff]), None);
+	});
+}
+
+/// Sets the timestamp of block 1 to 1 and then tries to set the one of block 2 to `now`.
+fn set_second_timestamp(now: u64) {
+	use frame_support::traits::OnFinalize;
+
+	assert_ok!(Timestamp::set(Origin::none(), 1));
+	<Timestamp as OnFinalize<BlockNumber>>::on_finalize(1);
+	System::set_block_number(2);
+	assert_ok!(Timestamp::set(Origin::none(), now));
+}
+
+#[test]
+fn minimum_period_defaults_to_half_the_slot_duration() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_eq!(MinimumPeriod::get(), SLOT_DURATION / 2);
+		assert_eq!(Aura::slot_duration(), SLOT_DURATION);
+		set_second_timestamp(1 + SLOT_DURATION / 2);
+	});
+}
+
+#[test]
+fn minimum_period_can_be_overridden_at_genesis() {
+	ExtBuilder::default().minimum_period(10_000).build().execute_with(|| {
+		assert_eq!(MinimumPeriod::get(), 10_000);
+		// Still within the first slot, which the timestamp has to match for Aura.
+		set_second_timestamp(10_001);
+	});
+}
+
+#[test]
+#[should_panic(expected = "Timestamp must increment")]
+fn timestamps_closer_than_the_overridden_minimum_period_are_rejected() {
+	ExtBuilder::default().minimum_period(10_000).build().execute_with(|| {
+		set_second_timestamp(10_000);
+	});
+}
+
+/// A contract which emits an event without data whose topics are the SCALE encoded
+/// `Vec<Hash>` passed as the input of the call.
+const TOPIC_EMITTER: &str = r#"
+(module
+	(import "seal0" "seal_input" (func $seal_input (param i32 i32)))
+	(import "seal0" "seal_deposit_event" (func $seal_deposit_event (param i32 i32 i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	;; [0, 4) the length of the input buffer
+	(data (i32.const 0) "\00\01")
+
+	(func (export "deploy"))
+	(func (export "call")
+		;; [4, 260) the input
+		(call $seal_input (i32.const 4) (i32.const 0))
+		(call $seal_deposit_event
+			(i32.const 4) ;; topics_ptr
+			(i32.load (i32.const 0)) ;; topics_len
+			(i32.const 0) ;; data_ptr
+			(i32.const 0) ;; data_len
+		)
+	)
+)
+"#;
+
+#[test]
+fn events_are_limited_to_max_event_topics() {
+	ExtBuilder::default().build().execute_with(|| {
+		let addr = instantiate(TOPIC_EMITTER, Contracts::subsistence_threshold());
+		let emit = |count: u8| {
+			let topics: Vec<Hash> = (1..=count).map(Hash::repeat_byte).collect();
+			Contracts::call(Origin::signed(alice()), addr.clone().into(), 0, GAS_LIMIT, topics.encode())
+				.map_err(|e| e.error)
+		};
+
+		assert_eq!(MaxEventTopics::get(), 4);
+		assert!(emit(4).is_ok());
+		assert_eq!(System::events().last().map(|r| r.topics.len()), Some(4));
+		assert_eq!(emit(5), Err(ContractsError::<Runtime>::TooManyTopics.into()));
+	});
+}
+
+#[test]
+fn next_timestamp_is_one_slot_after_the_current_one() {
+	use runtime_api::runtime_decl_for_CanvasSystemApi::CanvasSystemApi;
+
+	ExtBuilder::default().build().execute_with(|| {
+		pallet_timestamp::Now::<Runtime>::put(1_000);
+		assert_eq!(Runtime::next_timestamp(), 1_000 + SLOT_DURATION);
+	});
+}
+
+/// Starts block `number` like `initialize_block`, authored in Aura slot `number`.
+fn author_block(number: BlockNumber) {
+	let slot = sp_consensus_aura::Slot::from(number as u64);
+	let digest = generic::Digest {
+		logs: vec![generic::DigestItem::PreRuntime(sp_consensus_aura::AURA_ENGINE_ID, slot.encode())],
+	};
+	Executive::initialize_block(&Header::new(
+		number,
+		Default::default(),
+		Default::default(),
+		[number as u8; 32].into(),
+		digest,
+	));
+}
+
+#[test]
+fn block_authors_get_the_block_reward() {
+	let charlie = AccountKeyring::Charlie.to_account_id();
+
+	ExtBuilder::default()
+		.aura_authorities(vec![AccountKeyring::Charlie.public().into()])
+		.build()
+		.execute_with(|| {
+			assert_eq!(BlockReward::get(), 0);
+			author_block(2);
+			assert_eq!(Balances::free_balance(&charlie), 0);
+
+			BlockReward::set(&DOLLARS);
+			author_block(3);
+			assert_eq!(Balances::free_balance(&charlie), DOLLARS);
+			author_block(4);
+			assert_eq!(Balances::free_balance(&charlie), 2 * DOLLARS);
+		});
+}
+
+#[test]
+fn deletion_weight_limit_shrinks_after_a_full_block() {
+	use frame_support::traits::OnFinalize;
+
+	ExtBuilder::default().build().execute_with(|| {
+		let base = DeletionWeightBase::get();
+
+		// The genesis block is empty.
+		assert_eq!(ContractsMetrics::last_block_weight(), 0);
+		assert_eq!(DeletionWeightLimit::get(), 2 * base);
+
+		let max_block = RuntimeBlockWeights::get().max_block;
+		System::register_extra_weight_unchecked(max_block, DispatchClass::Mandatory);
+		<ContractsMetrics as OnFinalize<BlockNumber>>::on_finalize(1);
+		assert!(ContractsMetrics::last_block_weight() >= max_block);
+		assert_eq!(DeletionWeightLimit::get(), base - base / 2);
+	});
+}
+
+#[test]
+fn provenance_records_the_deployer_and_the_block() {
+	use runtime_api::runtime_decl_for_CanvasContractsApi::CanvasContractsApi;
+
+	ExtBuilder::default().build().execute_with(|| {
+		let code = wat::parse_str(DUMMY_CONTRACT).unwrap();
+		let code_hash = BlakeTwo256::hash(&code);
+		let endowment = Contracts::subsistence_threshold();
+
+		initialize_block(2);
+		let upload = Call::Contracts(ContractsCall::instantiate_with_code(
+			endowment,
+			GAS_LIMIT,
+			code,
+			vec![],
+			vec![],
+		));
+		assert_eq!(apply(AccountKeyring::Alice, upload), Ok(Ok(())));
+
+		initialize_block(3);
+		let instantiate = Call::Contracts(ContractsCall::instantiate(
+			endowment,
+			GAS_LIMIT,
+			code_hash,
+			vec![],
+			vec![],
+		));
+		assert_eq!(apply(AccountKeyring::Bob, instantiate), Ok(Ok(())));
+
+		let by_alice = Contracts::contract_address(&alice(), &code_hash, &[]);
+		let by_bob = Contracts::contract_address(&bob(), &code_hash, &[]);
+		assert_eq!(Runtime::provenance(by_alice), Some((alice(), 2)));
+		assert_eq!(Runtime::provenance(by_bob), Some((bob(), 3)));
+		assert_eq!(Runtime::provenance(AccountKeyring::Charlie.to_account_id()), None);
+	});
+}
+
+#[test]
+fn code_may_declare_at_most_max_memory_pages() {
+	ExtBuilder::default().build().execute_with(|| {
+		let upload = |max_pages: u32| {
+			let code = wat::parse_str(format!(
+				r#"(module
+					(import "env" "memory" (memory 1 {}))
+					(func (export "deploy"))
+					(func (export "call"))
+				)"#,
+				max_pages,
+			)).unwrap();
+			Contracts::instantiate_with_code(
+				Origin::signed(alice()),
+				Contracts::subsistence_threshold(),
+				GAS_LIMIT,
+				code,
+				vec![],
+				vec![],
+			).map_err(|e| e.error)
+		};
+
+		assert_eq!(MaxMemoryPages::get(), 16);
+		assert!(upload(16).is_ok());
+		assert_eq!(upload(17), Err(ContractsError::<Runtime>::CodeRejected.into()));
+	});
+}
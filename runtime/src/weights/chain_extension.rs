@@ -0,0 +1,31 @@
+//! Weights for the Canvas chain extension.
+//!
+//! PLACEHOLDER: these are not benchmark results. They are the estimates the extension
+//! charged before it had benchmarks and may underprice its functions. Replace this file
+//! with the output of the benchmarks in `crate::benchmarking`, run on the reference
+//! hardware:
+//!
+//! ```text
+//! canvas benchmark --chain=dev --execution=wasm --wasm-execution=compiled
+//!     --pallet=canvas_chain_extension --extrinsic='*' --steps=50 --repeat=20
+//!     --output=runtime/src/weights/chain_extension.rs
+//! ```
+
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::Weight};
+use sp_std::marker::PhantomData;
+
+/// Weight functions for the Canvas chain extension.
+pub struct WeightInfo<T>(PhantomData<T>);
+impl<T: frame_system::Config> crate::chain_extension::WeightInfo for WeightInfo<T> {
+	fn fetch_random() -> Weight {
+		(50_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+	}
+	fn recommended_tip() -> Weight {
+		(5_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+	}
+}
@@ -0,0 +1,3 @@
+//! Weights of the runtime code which is not part of a pallet.
+
+pub mod chain_extension;